@@ -0,0 +1,50 @@
+// localStorage for src/persistence.rs, load this after the miniquad bundle.
+// Keys are prefixed, so that other games on the same origin don't get in the way.
+const PERSISTENCE_PREFIX = "komisches-projekt/";
+// the string waiting to be copied over by persistence_take
+var persistence_pending = null;
+
+function persistence_string(ptr, len) {
+    return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+}
+
+function persistence_hand_over(string) {
+    if (string === null) {
+        persistence_pending = null;
+        return -1;
+    }
+    persistence_pending = new TextEncoder().encode(string);
+    return persistence_pending.length;
+}
+
+miniquad_add_plugin({
+    register_plugin: function (importObject) {
+        importObject.env.persistence_get = function (key_ptr, key_len) {
+            const key = PERSISTENCE_PREFIX + persistence_string(key_ptr, key_len);
+            return persistence_hand_over(window.localStorage.getItem(key));
+        };
+        importObject.env.persistence_set = function (key_ptr, key_len, value_ptr, value_len) {
+            const key = PERSISTENCE_PREFIX + persistence_string(key_ptr, key_len);
+            window.localStorage.setItem(key, persistence_string(value_ptr, value_len));
+        };
+        importObject.env.persistence_children = function (prefix_ptr, prefix_len) {
+            const prefix = PERSISTENCE_PREFIX + persistence_string(prefix_ptr, prefix_len);
+            const names = new Set();
+            for (let i = 0; i < window.localStorage.length; i++) {
+                const key = window.localStorage.key(i);
+                const rest = key.startsWith(prefix) ? key.slice(prefix.length) : "";
+                // only names that have keys below them, like directories
+                if (rest.includes("/")) {
+                    names.add(rest.split("/")[0]);
+                }
+            }
+            return persistence_hand_over(Array.from(names).join("\n"));
+        };
+        importObject.env.persistence_take = function (buf_ptr) {
+            new Uint8Array(wasm_memory.buffer, buf_ptr, persistence_pending.length).set(persistence_pending);
+            persistence_pending = null;
+        };
+    },
+    name: "persistence",
+    version: 1,
+});
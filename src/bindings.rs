@@ -0,0 +1,191 @@
+//! Which keys trigger which action, rebindable in the settings menu and saved across launches.
+
+use macroquad::prelude::*;
+
+use crate::{persistence, save::entries};
+
+/// not inside a profile, the keyboard belongs to the machine rather than to a player
+const BINDINGS_KEY: &str = "keybindings.txt";
+
+/// every action that can be rebound, F1 for the settings menu itself stays fixed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// click the button with keyboard focus
+    Activate,
+    /// move the keyboard focus on, backwards with Shift held
+    NextFocus,
+    SlowMotion,
+    SkipTransition,
+    /// back to the state before, see [`crate::simulation::Simulation::back`]
+    Back,
+    SelectProfile,
+    /// every creature so far, see [`crate::gallery`]
+    Gallery,
+    /// writes the runtime state out for a bug report, see [`crate::dump`]
+    DumpState,
+    /// shows the last few things that happened, see [`crate::event_log`]
+    EventLog,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Activate,
+        Action::NextFocus,
+        Action::SlowMotion,
+        Action::SkipTransition,
+        Action::Back,
+        Action::SelectProfile,
+        Action::Gallery,
+        Action::DumpState,
+        Action::EventLog,
+    ];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Action::Activate => "Press button",
+            Action::NextFocus => "Next button",
+            Action::SlowMotion => "Slow motion",
+            Action::SkipTransition => "Skip transition",
+            Action::Back => "Go back",
+            Action::SelectProfile => "Switch profile",
+            Action::Gallery => "Gallery",
+            Action::DumpState => "Dump state",
+            Action::EventLog => "Event log",
+        }
+    }
+
+    /// the name used in the saved file
+    fn key_name(&self) -> &'static str {
+        match self {
+            Action::Activate => "activate",
+            Action::NextFocus => "next-focus",
+            Action::SlowMotion => "slow-motion",
+            Action::SkipTransition => "skip-transition",
+            Action::Back => "back",
+            Action::SelectProfile => "select-profile",
+            Action::Gallery => "gallery",
+            Action::DumpState => "dump-state",
+            Action::EventLog => "event-log",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        use KeyCode::*;
+        match self {
+            Action::Activate => vec![Enter, KpEnter, Space],
+            Action::NextFocus => vec![Tab],
+            Action::SlowMotion => vec![LeftShift, RightShift],
+            Action::SkipTransition => vec![Escape],
+            Action::Back => vec![Backspace],
+            Action::SelectProfile => vec![F2],
+            Action::Gallery => vec![G],
+            Action::DumpState => vec![F12],
+            Action::EventLog => vec![F11],
+        }
+    }
+}
+
+/// The keys that can be bound, which is also how they're found again by name when loading.
+/// The keys with a fixed meaning are left out: F1 opens the settings menu, F3 to F10 are the cheats
+/// (see [`crate::cheats`]) and N/P step through the states with `--review`.
+#[rustfmt::skip]
+const BINDABLE_KEYS: [KeyCode; 59] = {
+    use KeyCode::*;
+    [
+        Space, Enter, KpEnter, Escape, Tab, Backspace, Insert, Delete, Home, End, PageUp, PageDown,
+        Up, Down, Left, Right, LeftShift, RightShift, LeftControl, RightControl, LeftAlt, RightAlt,
+        A, B, C, D, E, F, G, H, I, J, K, L, M, O, Q, R, S, T, U, V, W, X, Y, Z,
+        Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+        F2, F11, F12,
+    ]
+};
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .into_iter()
+        .find(|key| format!("{:?}", key) == name)
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+    /// indexed by `Action`
+    keys: [Vec<KeyCode>; Action::ALL.len()],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL.map(|action| action.default_keys()),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        &self.keys[action as usize]
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|&key| is_key_pressed(key))
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|&key| is_key_down(key))
+    }
+
+    /// Binds `key` to `action` alone, unless another action uses it already.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) -> Result<(), String> {
+        if !is_bindable(key) {
+            return Err(format!("{:?} can't be bound", key));
+        }
+        let taken_by = Action::ALL
+            .into_iter()
+            .find(|&other| other != action && self.keys(other).contains(&key));
+        if let Some(other) = taken_by {
+            return Err(format!(
+                "{:?} is already used for {}",
+                key,
+                other.display_name()
+            ));
+        }
+        self.keys[action as usize] = vec![key];
+        Ok(())
+    }
+
+    /// the saved bindings, with the defaults for anything missing
+    pub fn load() -> KeyBindings {
+        let mut bindings = KeyBindings::default();
+        let text = persistence::get(BINDINGS_KEY).unwrap_or_default();
+        for (name, value) in entries(&text) {
+            let Some(action) = Action::ALL.into_iter().find(|a| a.key_name() == name) else {
+                continue;
+            };
+            let keys: Vec<KeyCode> = value.split(',').filter_map(key_from_name).collect();
+            if !keys.is_empty() {
+                bindings.keys[action as usize] = keys;
+            }
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        let text: String = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys: Vec<String> = self
+                    .keys(action)
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect();
+                format!("{}={}\n", action.key_name(), keys.join(","))
+            })
+            .collect();
+        if let Err(e) = persistence::set(BINDINGS_KEY, &text) {
+            log_error!("{}", e);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! A plain horizontal bar chart, for looking at the stats.
+
+use macroquad::prelude::*;
+
+/// one row of the chart, its segments stacked from left to right
+pub struct Bar {
+    pub label: String,
+    pub segments: Vec<(f32, Color)>,
+}
+
+impl Bar {
+    fn total(&self) -> f32 {
+        self.segments.iter().map(|&(value, _)| value).sum()
+    }
+}
+
+/// the labels take up this share of the width, left of the bars
+const LABEL_SHARE: f32 = 0.3;
+
+/// Draws `bars` into `area` one below the other, scaled so that the longest one fills the width,
+/// each followed by its total.
+pub fn draw_bar_chart(area: Rect, bars: &[Bar]) {
+    if bars.is_empty() {
+        return;
+    }
+    let row_height = area.h / bars.len() as f32;
+    let bar_height = row_height * 0.7;
+    let text_size = bar_height * 0.8;
+    let bars_x = area.x + area.w * LABEL_SHARE;
+    // room for the total behind the longest bar
+    let bars_width = area.w * (1. - LABEL_SHARE) - text_size * 3.;
+    let longest = bars.iter().map(Bar::total).fold(0., f32::max);
+    let scale = if longest > 0. {
+        bars_width / longest
+    } else {
+        0.
+    };
+
+    for (i, bar) in bars.iter().enumerate() {
+        let y = area.y + i as f32 * row_height;
+        let text_y = y + bar_height * 0.8;
+        draw_text(&bar.label, area.x, text_y, text_size, WHITE);
+        let mut x = bars_x;
+        for &(value, color) in bar.segments.iter() {
+            let width = value * scale;
+            draw_rectangle(x, y, width, bar_height, color);
+            x += width;
+        }
+        draw_text(
+            &format!("{}", bar.total()),
+            x + text_size * 0.3,
+            text_y,
+            text_size,
+            WHITE,
+        );
+    }
+}
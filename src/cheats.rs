@@ -0,0 +1,202 @@
+//! Test affordances behind `--cheats`, kept apart from the normal input handling
+//! so that none of them can leak into a build without the flag.
+
+use macroquad::prelude::*;
+
+use crate::{
+    chart::{self, Bar},
+    ButtonType, World, WorldState, WORLD_HEIGHT, WORLD_WIDTH,
+};
+
+/// a key, how it's listed in the debug overlay, and what it does
+type Cheat = (KeyCode, &'static str, fn(&mut World));
+
+/// how far the world moves on per step while paused, in seconds
+pub const STEP_DELTA: f32 = 1. / 60.;
+
+/// one per button in the breakdown of [`World::draw_button_stats`], indexed by `ButtonType`
+const BUTTON_COLORS: [Color; 4] = [ORANGE, SKYBLUE, LIME, LIGHTGRAY];
+
+const CHEATS: [Cheat; 7] = [
+    (KeyCode::F3, "F3: toggle this overlay", toggle_overlay),
+    (KeyCode::F4, "F4: discover every creature", discover_all),
+    (KeyCode::F5, "F5: toggle button hitboxes", toggle_hitboxes),
+    (KeyCode::F6, "F6: pause", toggle_pause),
+    (KeyCode::F7, "F7: step 1/60 s while paused", step),
+    (KeyCode::F8, "F8: creatures per button", toggle_button_stats),
+    (
+        KeyCode::F10,
+        "F10: toggle the pixel-perfect view",
+        toggle_crisp,
+    ),
+];
+
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+const HOT_RELOAD_CHEATS: &[Cheat] = &[(KeyCode::F9, "F9: reload the state graph", reload_graph)];
+#[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+const HOT_RELOAD_CHEATS: &[Cheat] = &[];
+
+/// where F9 reads the state graph from, see [`crate::graph::StateGraph::parse`]
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+const GRAPH_FILE: &str = "assets/states.txt";
+
+/// every cheat there is in this build
+fn cheats() -> impl Iterator<Item = Cheat> {
+    CHEATS.into_iter().chain(HOT_RELOAD_CHEATS.iter().copied())
+}
+
+/// Swaps the live state graph for the one in `GRAPH_FILE`, keeping the old one if it doesn't validate.
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+fn reload_graph(world: &mut World) {
+    let result = std::fs::read_to_string(GRAPH_FILE)
+        .map_err(|e| crate::error::GameError::Asset {
+            path: GRAPH_FILE.to_string(),
+            message: e.to_string(),
+        })
+        .and_then(|text| crate::graph::StateGraph::parse(&text))
+        .and_then(|graph| world.sim.replace_graph(graph));
+    match result {
+        Ok(()) => log_info!("reloaded the state graph from {}", GRAPH_FILE),
+        Err(ref e) => log_error!("kept the old state graph: {}", e),
+    }
+    world.graph_error = result.err();
+}
+
+fn toggle_overlay(world: &mut World) {
+    world.debug_overlay = !world.debug_overlay;
+}
+
+fn toggle_hitboxes(world: &mut World) {
+    world.show_hitboxes = !world.show_hitboxes;
+}
+
+fn toggle_button_stats(world: &mut World) {
+    world.show_button_stats = !world.show_button_stats;
+}
+
+/// switches between the stretched and the pixel-perfect camera, the textures keep their filtering
+fn toggle_crisp(world: &mut World) {
+    world.config.crisp = !world.config.crisp;
+}
+
+fn toggle_pause(world: &mut World) {
+    world.set_paused(!world.paused);
+}
+
+fn step(world: &mut World) {
+    world.step_pending = world.paused;
+}
+
+fn discover_all(world: &mut World) {
+    for state in WorldState::ALL {
+        world.discovered.insert(state);
+    }
+    world.save();
+}
+
+impl World {
+    /// Only called with `--cheats`.
+    pub fn handle_cheat_input(&mut self) {
+        for (key, _, cheat) in cheats() {
+            if is_key_pressed(key) {
+                cheat(self);
+            }
+        }
+    }
+
+    /// the current state and the available cheats in the bottom left corner, and the hitboxes if toggled on
+    pub fn draw_debug_overlay(&self) {
+        if self.show_hitboxes {
+            for button in self.buttons.iter() {
+                button.draw_hitbox(self.sim.is_enabled(button.b_type));
+            }
+        }
+        if self.paused {
+            draw_text("paused", 60., 200., 120., WHITE);
+        }
+        if self.show_button_stats {
+            self.draw_button_stats();
+        }
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if let Some(ref e) = self.graph_error {
+            draw_text(&e.to_string(), 60., 320., 60., RED);
+        }
+        if !self.debug_overlay {
+            return;
+        }
+        let mut lines = vec![self.sim.state.display_name().to_string()];
+        if let Some(ref t) = self.sim.transition {
+            lines[0] += &format!(" -> {}", t.goal_state.display_name());
+        }
+        lines.push(format!("discovered: {}", self.discovered.iter().count()));
+        lines.push(format!(
+            "texture memory: {:.1} MB",
+            self.texture_memory_bytes() as f32 / 1e6
+        ));
+        if let Some(ref cache) = self.texture_cache {
+            let (resident, bytes, all) = cache.gpu_usage();
+            lines.push(format!(
+                "decoded textures: {}, {:.1} MB ({:.1} MB with every state)",
+                resident,
+                bytes as f32 / 1e6,
+                all as f32 / 1e6
+            ));
+        }
+        lines.extend(cheats().map(|(_, description, _)| description.to_string()));
+
+        let font_size = 80.;
+        let line_height = 100.;
+        let top = WORLD_HEIGHT - 80. - line_height * lines.len() as f32;
+        draw_rectangle(
+            0.,
+            top - 20.,
+            WORLD_WIDTH,
+            line_height * lines.len() as f32 + 80.,
+            Color::new(0., 0., 0., 0.6),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(
+                line,
+                60.,
+                top + line_height * (i as f32 + 1.),
+                font_size,
+                WHITE,
+            );
+        }
+    }
+
+    /// how often each leaf creature was reached, split up by the button that cracked its egg
+    fn draw_button_stats(&self) {
+        let bars: Vec<Bar> = WorldState::ALL
+            .into_iter()
+            .filter(|&state| self.sim.graph.is_leaf(state))
+            .map(|state| Bar {
+                label: state.display_name().to_string(),
+                segments: ButtonType::ALL
+                    .map(|button| {
+                        let count = self.stats.by_first_button[state as usize][button as usize];
+                        (count as f32, BUTTON_COLORS[button as usize])
+                    })
+                    .to_vec(),
+            })
+            .collect();
+        let area = Rect::new(200., 300., WORLD_WIDTH - 400., WORLD_HEIGHT - 1400.);
+        draw_rectangle(
+            area.x - 60.,
+            area.y - 160.,
+            area.w + 120.,
+            area.h + 220.,
+            Color::new(0., 0., 0., 0.8),
+        );
+        let legend: Vec<String> = ButtonType::ALL
+            .iter()
+            .map(|button| format!("{:?}", button))
+            .collect();
+        let mut x = area.x;
+        for (name, color) in legend.iter().zip(BUTTON_COLORS) {
+            draw_text(name, x, area.y - 60., 70., color);
+            x += measure_text(name, None, 70, 1.).width + 60.;
+        }
+        chart::draw_bar_chart(area, &bars);
+    }
+}
@@ -0,0 +1,60 @@
+//! A purely cosmetic combo counter for quickly chaining transitions one after another.
+
+use macroquad::prelude::*;
+
+use crate::WORLD_WIDTH;
+
+/// how long after a transition completes the next one still counts as part of the combo, in seconds
+const COMBO_WINDOW: f32 = 3.;
+/// how fast the pulse after an increment fades, per second
+const PULSE_DECAY: f32 = 2.5;
+
+#[derive(Default)]
+pub struct Combo {
+    count: u32,
+    /// time left to continue the combo, only runs while the world is at rest
+    window_left: f32,
+    /// 1 right after the count went up, fading to 0
+    pulse: f32,
+}
+
+impl Combo {
+    /// The world came to rest after a transition (and any egg cracks it continued into), the window opens.
+    pub fn on_rest(&mut self) {
+        self.window_left = COMBO_WINDOW;
+    }
+
+    /// The player started a transition.
+    pub fn on_trigger(&mut self) {
+        if self.window_left > 0. {
+            self.count += 1;
+            self.pulse = 1.;
+        } else {
+            self.count = 0;
+        }
+        self.window_left = 0.;
+    }
+
+    pub fn update(&mut self, delta_secs: f32) {
+        self.window_left = (self.window_left - delta_secs).max(0.);
+        self.pulse = (self.pulse - delta_secs * PULSE_DECAY).max(0.);
+    }
+
+    /// a number in the top right corner, growing with the count and pulsing on every increment
+    pub fn draw(&self) {
+        if self.count == 0 {
+            return;
+        }
+        let text = format!("x{}", self.count);
+        let size = (140. + 20. * self.count.min(10) as f32) * (1. + 0.4 * self.pulse);
+        let dims = measure_text(&text, None, size as u16, 1.);
+        let color = Color::new(1., 0.85 + 0.15 * self.pulse, 0.3 + 0.7 * self.pulse, 1.);
+        draw_text(
+            &text,
+            WORLD_WIDTH - dims.width - 120.,
+            120. + dims.offset_y,
+            size,
+            color,
+        );
+    }
+}
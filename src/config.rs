@@ -0,0 +1,821 @@
+//! Options the game can be started with, read from the command line.
+
+use macroquad::{
+    color::{Color, BLACK, WHITE},
+    math::{vec2, Rect, Vec2},
+};
+
+use crate::{
+    error::GameError, profile::Profile, simulation::NavigationMode, ButtonLayout, ButtonType,
+    DisabledButtons, SoundIndex, TransitionType, WorldState, WORLD_HEIGHT, WORLD_STATE_VARIANTS,
+    WORLD_WIDTH,
+};
+
+/// where `--dump-graph` writes to without a file name
+const DEFAULT_GRAPH_FILE: &str = "state-graph.dot";
+const DEFAULT_BENCH_FRAMES: usize = 1000;
+
+#[derive(Clone)]
+pub struct Config {
+    /// QA mode for art review: N/P step through every state directly, buttons and transitions are off
+    pub review: bool,
+    /// no sounds are played at all
+    pub mute: bool,
+    /// master volume for sound effects and music, 1 is the original loudness
+    pub volume: f32,
+    /// a soft blip whenever the mouse moves onto a button, off by default since it gets old quickly
+    pub hover_sound: bool,
+    /// soft drop shadows behind the creatures and buttons
+    pub shadows: bool,
+    /// particles drifting around each state while it's shown, indexed by `WorldState`, None for none
+    pub ambience: [Option<Emitter>; WORLD_STATE_VARIANTS],
+    /// the whole screen flashes as each egg crack is done, None for no flash
+    pub crack_flash: Option<Flash>,
+    pub button_layout: ButtonLayout,
+    pub disabled_buttons: DisabledButtons,
+    /// the smallest the buttons may get on screen, in logical pixels, they grow on small windows to keep it
+    pub min_tap_size: f32,
+    /// how far the world is kept from every edge of the window, as a fraction of its size, see [`crate::view`]
+    pub safe_area: f32,
+    /// how many seconds without any input it takes before a button leading somewhere new is hinted at
+    pub hint_delay: f32,
+    /// how many seconds without any input it takes at an egg before a prompt to start fades in over it
+    pub attract_delay: f32,
+    /// holding a button this many seconds peeks at where it leads instead of clicking it, None to always click
+    pub long_press: Option<f32>,
+    /// what it takes for a press and release to count as a click, with the mouse and with a finger
+    pub taps: TapThresholds,
+    /// MSAA samples per pixel asked of the window, smoothing the edges of everything drawn, 1 for none
+    pub msaa: i32,
+    /// what's behind the world, a translucent one makes the window itself translucent where the platform
+    /// supports it, for compositing the game over something else
+    pub clear_color: Color,
+    /// the loading screen stays up at least this many seconds, so that it doesn't just flash by
+    pub min_loading_time: f32,
+    pub loading_screen: LoadingScreen,
+    pub logo: LogoConfig,
+    /// columns and rows of `assets/states.png`, holding every state in one image in index order, row by row
+    pub spritesheet: Option<(u32, u32)>,
+    /// how many state textures may be decoded at once, see [`crate::texture_cache`]
+    pub texture_cache: Option<usize>,
+    /// how many bytes of GPU memory the state textures closest to the current one may be decoded into
+    /// ahead of time with `--texture-cache`, see [`crate::World::warm_textures`]
+    pub texture_budget: Option<usize>,
+    /// load the textures one after another in index order, slower but the same every time
+    pub sequential_loading: bool,
+    /// the system cursor instead of `assets/cursor.png`
+    pub native_cursor: bool,
+    /// nearest filtering and whole-number scaling instead of the intentionally blurry look
+    pub crisp: bool,
+    /// instead of playing, render the transition from a state via a button into PNGs, see [`crate::export`]
+    pub export_transition: Option<(WorldState, ButtonType)>,
+    /// instead of playing, render every state into one labeled PNG, see [`crate::export`]
+    pub contact_sheet: bool,
+    /// instead of playing, time rendering this many frames at rest and during a transition, see [`crate::export::bench_render`]
+    pub bench_render: Option<usize>,
+    /// instead of playing, check that every state texture has the world's aspect ratio and exit with 1 if not
+    pub validate_assets: bool,
+    /// instead of playing, write the state graph as Graphviz DOT to this file, see [`crate::graph::StateGraph::to_dot`]
+    pub dump_graph: Option<String>,
+    /// click through the commands in this file, see [`crate::script`]
+    pub script: Option<String>,
+    /// log every session into a JSON file when quitting, see [`crate::session`]
+    pub log_sessions: bool,
+    /// which profile to play with, instead of asking at launch
+    pub profile: Option<Profile>,
+    /// start on a screen choosing between the small and the big egg, see [`crate::egg_selection`]
+    pub choose_egg: bool,
+    /// another button can redirect a running regular transition, see [`crate::simulation::Simulation::click`]
+    pub interruptible: bool,
+    /// Restart walks back along the way to the creature instead of going straight to the egg,
+    /// see [`crate::simulation::Simulation::unwind`]
+    pub unwind: bool,
+    /// regular transitions play a melody up and down the scale instead of random notes,
+    /// see [`crate::simulation::Simulation::melody_notes`]
+    pub melody: bool,
+    /// time how fast this creature is reached from the egg, see [`crate::speedrun`]
+    pub speedrun: Option<WorldState>,
+    /// the chance for the creatures hatching from an egg to be shiny, drawn in golden colors
+    pub shiny_chance: f32,
+    /// send the player looking for a leaf creature picked by the date, see [`crate::daily`]
+    pub creature_of_the_day: bool,
+    /// normal, or a sandbox sending the buttons to fixed creatures
+    pub navigation: NavigationMode,
+    /// multiplier on how fast transitions play, 2 means twice as fast
+    pub transition_speed: f32,
+    pub transitions: TransitionConfig,
+    /// where each state's texture is drawn, for art that doesn't fill the whole canvas
+    pub placements: [Placement; WORLD_STATE_VARIANTS],
+    /// files in the asset directory played as a creature is revealed, indexed by `WorldState`, None for silence
+    pub reveal_sounds: [Option<String>; WORLD_STATE_VARIANTS],
+    /// test affordances like discovering everything at once, see [`crate::cheats`]
+    pub cheats: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            review: false,
+            mute: false,
+            volume: 1.,
+            hover_sound: false,
+            shadows: false,
+            ambience: [None; WORLD_STATE_VARIANTS],
+            crack_flash: None,
+            button_layout: ButtonLayout::Row,
+            disabled_buttons: DisabledButtons::Hide,
+            min_tap_size: 44.,
+            safe_area: 0.,
+            hint_delay: 20.,
+            long_press: None,
+            taps: TapThresholds::default(),
+            attract_delay: 60.,
+            msaa: 1,
+            clear_color: BLACK,
+            min_loading_time: 0.8,
+            loading_screen: LoadingScreen::default(),
+            logo: LogoConfig::default(),
+            spritesheet: None,
+            texture_cache: None,
+            texture_budget: None,
+            sequential_loading: false,
+            native_cursor: false,
+            crisp: false,
+            export_transition: None,
+            contact_sheet: false,
+            bench_render: None,
+            validate_assets: false,
+            dump_graph: None,
+            script: None,
+            log_sessions: false,
+            profile: None,
+            choose_egg: false,
+            interruptible: false,
+            unwind: false,
+            melody: false,
+            speedrun: None,
+            creature_of_the_day: false,
+            shiny_chance: 1. / 64.,
+            navigation: NavigationMode::Normal,
+            transition_speed: 1.,
+            transitions: TransitionConfig::default(),
+            placements: [Placement::default(); WORLD_STATE_VARIANTS],
+            reveal_sounds: Default::default(),
+            cheats: false,
+        }
+    }
+}
+
+impl Config {
+    /// Options are given as `--name` or `--name=value`, e.g. `--regular-sound-at=0.5`.
+    pub fn from_args() -> Self {
+        let mut config = Config::default();
+        for arg in std::env::args().skip(1) {
+            if let Err(message) = config.apply(&arg) {
+                log_warn!("ignoring {}", GameError::Config { arg, message });
+            }
+        }
+        if config.texture_budget.is_some() && config.texture_cache.is_none() {
+            let arg = "--texture-budget".to_string();
+            let message = "there's nothing to warm without --texture-cache".to_string();
+            log_warn!("ignoring {}", GameError::Config { arg, message });
+            config.texture_budget = None;
+        }
+        config
+    }
+
+    /// the master volume, or 0 when muted
+    pub fn effective_volume(&self) -> f32 {
+        if self.mute {
+            0.
+        } else {
+            self.volume
+        }
+    }
+
+    /// the loading screen's own background, or the clear color
+    pub fn loading_background(&self) -> Color {
+        self.loading_screen.background.unwrap_or(self.clear_color)
+    }
+
+    fn apply(&mut self, arg: &str) -> Result<(), String> {
+        let Some(option) = arg.strip_prefix("--") else {
+            return Err("options have to start with --".to_string());
+        };
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+        match (name, value) {
+            ("review", None) => self.review = true,
+            ("mute", None) => self.mute = true,
+            ("volume", Some(v)) => self.volume = parse_fraction(v)?,
+            ("hover-sound", None) => self.hover_sound = true,
+            ("shadows", None) => self.shadows = true,
+            ("ambience", Some(v)) => {
+                let (state, emitter) = v.split_once(',').ok_or(
+                    "expected <state>,<per second>,<color>[,<vx>,<vy>], e.g. nessi,4,a0d8ff,0,-150",
+                )?;
+                let state = WorldState::from_name(state)
+                    .ok_or_else(|| format!("unknown state {}", state))?;
+                self.ambience[state as usize] = Some(parse_emitter(emitter)?);
+            }
+            ("crack-flash", None) => self.crack_flash = Some(Flash::default()),
+            ("crack-flash", Some(v)) => self.crack_flash = Some(parse_flash(v)?),
+            ("cheats", None) => self.cheats = true,
+            ("crisp", None) => self.crisp = true,
+            ("native-cursor", None) => self.native_cursor = true,
+            ("texture-budget", Some(v)) => {
+                self.texture_budget = Some((parse_positive(v)? * 1e6) as usize)
+            }
+            ("texture-cache", Some(v)) => match v.parse::<usize>() {
+                Ok(capacity) if capacity >= 2 => self.texture_cache = Some(capacity),
+                _ => {
+                    return Err(format!(
+                        "expected at least 2 textures, for crossfading, got {}",
+                        v
+                    ))
+                }
+            },
+            ("spritesheet", Some(v)) => {
+                let grid = v.split_once('x').and_then(|(columns, rows)| {
+                    Some((columns.parse::<u32>().ok()?, rows.parse::<u32>().ok()?))
+                });
+                match grid {
+                    Some((columns, rows)) if columns > 0 && rows > 0 => {
+                        self.spritesheet = Some((columns, rows))
+                    }
+                    _ => return Err(format!("expected <columns>x<rows>, e.g. 5x4, got {}", v)),
+                }
+            }
+            ("sequential-loading", None) => self.sequential_loading = true,
+            ("min-loading-time", Some(v)) => self.min_loading_time = parse_non_negative(v)?,
+            ("clear-color", Some("transparent")) => self.clear_color = Color::new(0., 0., 0., 0.),
+            ("clear-color", Some(v)) => self.clear_color = parse_color(v)?,
+            ("loading-background", Some(v)) => {
+                self.loading_screen.background = Some(parse_color(v)?)
+            }
+            ("loading-text-color", Some(v)) => self.loading_screen.text_color = parse_color(v)?,
+            ("loading-text-size", Some(v)) => self.loading_screen.text_size = parse_positive(v)?,
+            ("loading-text-pos", Some(v)) => {
+                let (x, y) = v
+                    .split_once(',')
+                    .ok_or("expected <x>,<y> in world units, e.g. 400,1600")?;
+                let parse = |v: &str| {
+                    v.parse::<f32>()
+                        .map_err(|_| format!("expected a position in world units, got {}", v))
+                };
+                self.loading_screen.text_pos = Some(vec2(parse(x)?, parse(y)?));
+            }
+            ("min-tap-size", Some(v)) => self.min_tap_size = parse_positive(v)?,
+            ("safe-area", Some(v)) => {
+                self.safe_area = match v.parse::<f32>() {
+                    Ok(inset) if (0. ..0.45).contains(&inset) => inset,
+                    _ => return Err(format!("expected a fraction from 0 to 0.45, got {}", v)),
+                }
+            }
+            ("hint-delay", Some(v)) => self.hint_delay = parse_non_negative(v)?,
+            ("msaa", Some(v)) => match v.parse::<i32>() {
+                Ok(samples) if [1, 2, 4, 8, 16].contains(&samples) => self.msaa = samples,
+                _ => return Err(format!("expected 1, 2, 4, 8 or 16 samples, got {}", v)),
+            },
+            ("attract-delay", Some(v)) => self.attract_delay = parse_non_negative(v)?,
+            ("long-press", None) => self.long_press = Some(crate::LONG_PRESS),
+            ("long-press", Some(v)) => self.long_press = Some(parse_positive(v)?),
+            ("tap-threshold", Some(v)) => {
+                let usage = "expected <mouse|touch>,<secs>[,<travel>], e.g. touch,0.05,20";
+                let mut parts = v.split(',');
+                let tap = match parts.next() {
+                    Some("mouse") => &mut self.taps.mouse,
+                    Some("touch") => &mut self.taps.touch,
+                    _ => return Err(usage.to_string()),
+                };
+                tap.min_press = parse_non_negative(parts.next().ok_or(usage)?)?;
+                if let Some(travel) = parts.next() {
+                    tap.max_travel = parse_positive(travel)?;
+                }
+                if parts.next().is_some() {
+                    return Err(usage.to_string());
+                }
+            }
+            ("layout", Some(v)) => {
+                self.button_layout =
+                    ButtonLayout::from_name(v).ok_or_else(|| format!("unknown layout {}", v))?
+            }
+            ("disabled-buttons", Some(v)) => {
+                self.disabled_buttons = DisabledButtons::from_name(v)
+                    .ok_or_else(|| format!("expected hide or grey, got {}", v))?
+            }
+            ("no-logo", None) => self.logo.shown = false,
+            ("logo-pos", Some(v)) => {
+                let (x, y) = v
+                    .split_once(',')
+                    .ok_or("expected <x>,<y> in world units, e.g. 100,100")?;
+                let parse = |v: &str| {
+                    v.parse::<f32>()
+                        .map_err(|_| format!("expected a position in world units, got {}", v))
+                };
+                self.logo.pos = vec2(parse(x)?, parse(y)?);
+            }
+            ("logo-size", Some(v)) => self.logo.width = parse_positive(v)?,
+            ("logo-opacity", Some(v)) => self.logo.opacity = parse_fraction(v)?,
+            ("contact-sheet", None) => {
+                self.contact_sheet = true;
+                self.logo.shown = false;
+                self.mute = true;
+                self.native_cursor = true;
+            }
+            ("validate-assets", None) => self.validate_assets = true,
+            ("bench-render", None) => self.bench_render = Some(DEFAULT_BENCH_FRAMES),
+            ("bench-render", Some(v)) => match v.parse::<usize>() {
+                Ok(frames) if frames > 0 => self.bench_render = Some(frames),
+                _ => return Err(format!("expected a number of frames, got {}", v)),
+            },
+            ("export-transition", Some(v)) => {
+                self.export_transition = Some(parse_state_and_button(v)?);
+                // exporting runs a lot faster than real time, which would just make a mess of the sounds
+                self.mute = true;
+                // the mouse (and the branding) has no business in the exported frames
+                self.native_cursor = true;
+                self.logo.shown = false;
+            }
+            ("dump-graph", None) => self.dump_graph = Some(DEFAULT_GRAPH_FILE.to_string()),
+            ("dump-graph", Some(v)) => self.dump_graph = Some(v.to_string()),
+            ("log-sessions", None) => self.log_sessions = true,
+            ("script", Some(v)) => self.script = Some(v.to_string()),
+            ("profile", Some(v)) => self.profile = Some(Profile::new(v)?),
+            ("choose-egg", None) => self.choose_egg = true,
+            ("interruptible", None) => self.interruptible = true,
+            ("unwind", None) => self.unwind = true,
+            ("melody", None) => self.melody = true,
+            ("shiny-chance", Some(v)) => self.shiny_chance = parse_fraction(v)?,
+            ("creature-of-the-day", None) => self.creature_of_the_day = true,
+            ("speedrun", Some(v)) => {
+                self.speedrun =
+                    Some(WorldState::from_name(v).ok_or_else(|| format!("unknown state {}", v))?)
+            }
+            ("sandbox", Some(v)) => self.navigation = parse_sandbox(v)?,
+            ("transition-speed", Some(v)) => self.transition_speed = parse_positive(v)?,
+            ("regular-sound-at", Some(v)) => self.transitions.regular.sound_at = parse_fraction(v)?,
+            ("crack-sound-at", Some(v)) => {
+                self.transitions.egg_cracking.sound_at = parse_fraction(v)?
+            }
+            ("crossfade-overlap", Some(v)) => {
+                self.transitions.crossfade_overlap = parse_fraction(v)?
+            }
+            ("reveal-sound", Some(v)) => {
+                let (state, file) = v
+                    .split_once(',')
+                    .ok_or("expected <state>,<file>, e.g. dragonmander,roar.mp3")?;
+                let state = WorldState::from_name(state)
+                    .ok_or_else(|| format!("unknown state {}", state))?;
+                self.reveal_sounds[state as usize] = Some(file.to_string());
+            }
+            ("duration", Some(v)) => {
+                let (state, secs) = v
+                    .split_once(',')
+                    .ok_or("expected <state>,<seconds>, e.g. dragonmander,14")?;
+                let state = WorldState::from_name(state)
+                    .ok_or_else(|| format!("unknown state {}", state))?;
+                self.transitions.durations[state as usize] = Some(parse_positive(secs)?);
+            }
+            ("morph", Some(v)) => {
+                let (from, to) = v
+                    .split_once(',')
+                    .ok_or("expected <from>,<to>, e.g. eggcrack2,chick")?;
+                let state = |name| {
+                    WorldState::from_name(name).ok_or_else(|| format!("unknown state {}", name))
+                };
+                self.transitions.morphs.push((state(from)?, state(to)?));
+            }
+            ("morph-duration", Some(v)) => self.transitions.morph.duration = parse_positive(v)?,
+            ("return", Some(v)) => {
+                let (state, style) = v.split_once(',').ok_or(
+                    "expected <state>,<seconds>[,<seconds of black>[,<sound>]], e.g. duck,12,2,celebrate",
+                )?;
+                let state = WorldState::from_name(state)
+                    .ok_or_else(|| format!("unknown state {}", state))?;
+                self.transitions.returns[state as usize] = parse_return(style)?;
+            }
+            ("placement", Some(v)) => {
+                let (state, placement) = v
+                    .split_once(',')
+                    .ok_or("expected <state>,<scale>[,<x>,<y>], e.g. chick,0.5,0,300")?;
+                let state = WorldState::from_name(state)
+                    .ok_or_else(|| format!("unknown state {}", state))?;
+                self.placements[state as usize] = parse_placement(placement)?;
+            }
+            _ => return Err("unknown option".to_string()),
+        }
+        Ok(())
+    }
+}
+
+/// parses `<state>,<button>`, e.g. `egg,sun`
+fn parse_state_and_button(value: &str) -> Result<(WorldState, ButtonType), String> {
+    let (state, button) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected <state>,<button>, got {}", value))?;
+    let state = WorldState::from_name(state).ok_or_else(|| format!("unknown state {}", state))?;
+    let button =
+        ButtonType::from_name(button).ok_or_else(|| format!("unknown button {}", button))?;
+    Ok((state, button))
+}
+
+/// parses `<button>:<state>,...`, e.g. `sun:dragonmander,water:jellyfish`
+fn parse_sandbox(value: &str) -> Result<NavigationMode, String> {
+    let mut goals = [None; 4];
+    for mapping in value.split(',') {
+        let (button, state) = mapping
+            .split_once(':')
+            .ok_or_else(|| format!("expected <button>:<state>, got {}", mapping))?;
+        let button =
+            ButtonType::from_name(button).ok_or_else(|| format!("unknown button {}", button))?;
+        let state =
+            WorldState::from_name(state).ok_or_else(|| format!("unknown state {}", state))?;
+        goals[button as usize] = Some(state);
+    }
+    Ok(NavigationMode::Sandbox(goals))
+}
+
+/// parses `<seconds>[,<seconds of black>[,<sound>]]`, e.g. `12,2,celebrate`
+fn parse_return(value: &str) -> Result<ReturnTransition, String> {
+    let mut parts = value.split(',');
+    let duration = Some(parse_positive(parts.next().unwrap_or_default())?);
+    let black = parts.next().map_or(Ok(0.), parse_non_negative)?;
+    let sound = match parts.next() {
+        None | Some("scale") => ReturnSound::Scale,
+        Some("silent") => ReturnSound::Silent,
+        Some("crack") => ReturnSound::Play(SoundIndex::Crack2),
+        Some("celebrate") => ReturnSound::Play(SoundIndex::Celebrate),
+        Some(other) => {
+            return Err(format!(
+                "expected scale, silent, crack or celebrate as the sound, got {}",
+                other
+            ))
+        }
+    };
+    if parts.next().is_some() {
+        return Err(format!(
+            "expected <seconds>[,<seconds of black>[,<sound>]], got {}",
+            value
+        ));
+    }
+    Ok(ReturnTransition {
+        duration,
+        black,
+        sound,
+    })
+}
+
+/// parses `<per second>,<color>[,<vx>,<vy>]`, e.g. `4,a0d8ff,0,-150`
+fn parse_emitter(value: &str) -> Result<Emitter, String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    let velocity = match parts[..] {
+        [_, _] => Emitter::RISING,
+        [_, _, vx, vy] => match (vx.parse::<f32>(), vy.parse::<f32>()) {
+            (Ok(vx), Ok(vy)) => vec2(vx, vy),
+            _ => return Err(format!("expected a velocity, got {},{}", vx, vy)),
+        },
+        _ => {
+            return Err(format!(
+                "expected <per second>,<color>[,<vx>,<vy>], got {}",
+                value
+            ))
+        }
+    };
+    Ok(Emitter {
+        rate: parse_positive(parts[0])?,
+        color: parse_color(parts[1])?,
+        velocity,
+    })
+}
+
+/// parses `<intensity>[,<seconds>[,<color>]]`, e.g. `0.8,0.3,ffe080`
+fn parse_flash(value: &str) -> Result<Flash, String> {
+    let mut parts = value.split(',');
+    let mut flash = Flash {
+        intensity: parse_fraction(parts.next().unwrap_or_default())?,
+        ..Default::default()
+    };
+    if let Some(secs) = parts.next() {
+        flash.duration = parse_positive(secs)?;
+    }
+    if let Some(color) = parts.next() {
+        flash.color = parse_color(color)?;
+    }
+    if parts.next().is_some() {
+        return Err(format!(
+            "expected <intensity>[,<seconds>[,<color>]], got {}",
+            value
+        ));
+    }
+    Ok(flash)
+}
+
+/// parses `<scale>[,<x>,<y>]`, e.g. `0.5,0,300`
+fn parse_placement(value: &str) -> Result<Placement, String> {
+    let mut parts = value.split(',');
+    let scale = parse_positive(parts.next().unwrap_or_default())?;
+    let offset = match (parts.next(), parts.next(), parts.next()) {
+        (None, None, None) => Vec2::ZERO,
+        (Some(x), Some(y), None) => {
+            let parse = |v: &str| {
+                v.parse::<f32>()
+                    .map_err(|_| format!("expected an offset in world units, got {}", v))
+            };
+            vec2(parse(x)?, parse(y)?)
+        }
+        _ => return Err(format!("expected <scale>[,<x>,<y>], got {}", value)),
+    };
+    Ok(Placement { scale, offset })
+}
+
+/// parses a color like `#1e90ff` or `1e90ff`, or with an alpha like `#1e90ff80`
+fn parse_color(value: &str) -> Result<Color, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    match u32::from_str_radix(hex, 16) {
+        Ok(rgb) if hex.len() == 6 => Ok(Color::from_hex(rgb)),
+        Ok(rgba) if hex.len() == 8 => {
+            let [r, g, b, a] = rgba.to_be_bytes();
+            Ok(Color::from_rgba(r, g, b, a))
+        }
+        _ => Err(format!("expected a color like #1e90ff, got {}", value)),
+    }
+}
+
+fn parse_positive(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(f) if f > 0. && f.is_finite() => Ok(f),
+        _ => Err(format!("expected a positive number, got {}", value)),
+    }
+}
+
+fn parse_non_negative(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(f) if f >= 0. && f.is_finite() => Ok(f),
+        _ => Err(format!("expected a number of at least 0, got {}", value)),
+    }
+}
+
+fn parse_fraction(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(f) if (0. ..=1.).contains(&f) => Ok(f),
+        _ => Err(format!("expected a number between 0 and 1, got {}", value)),
+    }
+}
+
+/// How the loading screen looks.
+#[derive(Clone)]
+pub struct LoadingScreen {
+    /// the clear color if it isn't set, see [`Config::loading_background`]
+    pub background: Option<Color>,
+    pub text_color: Color,
+    pub text_size: f32,
+    /// where the text's baseline starts in the world, None to center it
+    pub text_pos: Option<Vec2>,
+}
+
+impl Default for LoadingScreen {
+    fn default() -> Self {
+        LoadingScreen {
+            background: None,
+            text_color: WHITE,
+            text_size: 200.,
+            text_pos: None,
+        }
+    }
+}
+
+/// The logo in the corner, see [`crate::overlay`].
+#[derive(Clone)]
+pub struct LogoConfig {
+    pub shown: bool,
+    /// the top left corner, in world units
+    pub pos: Vec2,
+    /// in world units, the height keeps the logo's proportions
+    pub width: f32,
+    pub opacity: f32,
+}
+
+impl Default for LogoConfig {
+    fn default() -> Self {
+        LogoConfig {
+            shown: true,
+            pos: vec2(WORLD_WIDTH - 500., 100.),
+            width: 400.,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// Where a state's texture is drawn: scaled around the center of the world, then moved by `offset`.
+/// The default fills the whole world, like the art is made to.
+#[derive(Clone, Copy)]
+pub struct Placement {
+    pub scale: f32,
+    /// in world units
+    pub offset: Vec2,
+}
+
+impl Placement {
+    /// the rect in the world the texture is drawn into
+    pub fn dest(&self) -> Rect {
+        let size = vec2(WORLD_WIDTH, WORLD_HEIGHT) * self.scale;
+        let pos = (vec2(WORLD_WIDTH, WORLD_HEIGHT) - size) / 2. + self.offset;
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            scale: 1.,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+/// the shortest a transition can take in seconds, however short it's configured
+const MIN_DURATION: f32 = 0.001;
+
+#[derive(Clone, Copy)]
+pub struct TransitionTiming {
+    /// in seconds
+    pub duration: f32,
+    /// when the transition sound is played, as a fraction of the duration
+    pub sound_at: f32,
+}
+
+#[derive(Clone)]
+pub struct TransitionConfig {
+    pub regular: TransitionTiming,
+    pub egg_cracking: TransitionTiming,
+    pub intro: TransitionTiming,
+    pub morph: TransitionTiming,
+    /// the edges that morph instead of crossfading, from and to, see [`TransitionType::Morph`]
+    pub morphs: Vec<(WorldState, WorldState)>,
+    /// how far the two fades of a regular transition overlap, from 0 (one after the other) to 1 (at once)
+    pub crossfade_overlap: f32,
+    /// longer or shorter regular transitions into specific states, indexed by `WorldState`
+    pub durations: [Option<f32>; WORLD_STATE_VARIANTS],
+    /// how each leaf goes back to its egg, indexed by `WorldState`
+    pub returns: [ReturnTransition; WORLD_STATE_VARIANTS],
+}
+
+/// How a leaf goes back to its egg, set with `--return`. By default that's a regular transition into the egg.
+#[derive(Clone, Copy, Default)]
+pub struct ReturnTransition {
+    /// in seconds, instead of that of a regular transition into the egg
+    pub duration: Option<f32>,
+    /// seconds of black between the leaf fading out and the egg fading in
+    pub black: f32,
+    pub sound: ReturnSound,
+}
+
+/// what plays during a return to the egg, at the usual point of a regular transition
+#[derive(Clone, Copy, Default)]
+pub enum ReturnSound {
+    /// a scale note, like any regular transition
+    #[default]
+    Scale,
+    Silent,
+    Play(SoundIndex),
+}
+
+/// Ambient particles around a state, like bubbles rising around the water creatures, set with `--ambience`.
+#[derive(Clone, Copy)]
+pub struct Emitter {
+    /// how many particles appear per second
+    pub rate: f32,
+    pub color: Color,
+    /// in world units per second, each particle's varies a little
+    pub velocity: Vec2,
+}
+
+impl Emitter {
+    /// slowly floating upwards, for when no velocity is given
+    const RISING: Vec2 = vec2(0., -120.);
+}
+
+/// A full-screen flash that fades out right away, set with `--crack-flash`.
+#[derive(Clone, Copy)]
+pub struct Flash {
+    /// the alpha it starts at
+    pub intensity: f32,
+    /// how many seconds it takes to fade out
+    pub duration: f32,
+    pub color: Color,
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Self {
+            intensity: 0.6,
+            duration: 0.25,
+            color: WHITE,
+        }
+    }
+}
+
+/// Set with `--tap-threshold`, separately for the mouse and for touch, since brushing a touchscreen
+/// by accident is a lot more likely than clicking by accident.
+#[derive(Clone, Copy, Default)]
+pub struct TapThresholds {
+    pub mouse: TapThreshold,
+    pub touch: TapThreshold,
+}
+
+/// What a press and release over a button has to look like to count as a click, anything else is ignored.
+#[derive(Clone, Copy)]
+pub struct TapThreshold {
+    /// how many seconds the button has to be held down at least
+    pub min_press: f32,
+    /// how far the pointer may move away from where it went down, in world units
+    pub max_travel: f32,
+}
+
+impl Default for TapThreshold {
+    /// every press and release clicks
+    fn default() -> Self {
+        Self {
+            min_press: 0.,
+            max_travel: f32::INFINITY,
+        }
+    }
+}
+
+impl TransitionConfig {
+    /// The timing of a transition into `goal_state`, the sound still plays at the same fraction of it.
+    /// Durations are at least `MIN_DURATION`, so that nothing ever divides by 0.
+    pub fn timing(&self, t_type: TransitionType, goal_state: WorldState) -> TransitionTiming {
+        let timing = match t_type {
+            TransitionType::Regular(_) => TransitionTiming {
+                duration: self.durations[goal_state as usize].unwrap_or(self.regular.duration),
+                ..self.regular
+            },
+            TransitionType::Return(leaf) => TransitionTiming {
+                duration: self.returns[leaf as usize]
+                    .duration
+                    .or(self.durations[goal_state as usize])
+                    .unwrap_or(self.regular.duration),
+                ..self.regular
+            },
+            TransitionType::EggCracking(_) => self.egg_cracking,
+            TransitionType::Intro => self.intro,
+            TransitionType::Morph(_) => TransitionTiming {
+                duration: self.durations[goal_state as usize].unwrap_or(self.morph.duration),
+                ..self.morph
+            },
+        };
+        TransitionTiming {
+            duration: timing.duration.max(MIN_DURATION),
+            ..timing
+        }
+    }
+
+    /// whether going from `from` to `to` morphs instead of crossfading
+    pub fn morphs(&self, from: WorldState, to: WorldState) -> bool {
+        self.morphs.contains(&(from, to))
+    }
+
+    /// the return style for returns to the egg, the default that changes nothing for the rest
+    pub fn return_style(&self, t_type: TransitionType) -> ReturnTransition {
+        match t_type {
+            TransitionType::Return(leaf) => self.returns[leaf as usize],
+            _ => ReturnTransition::default(),
+        }
+    }
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            // the scale note plays a little before the midpoint of the crossfade
+            regular: TransitionTiming {
+                duration: 9.3,
+                sound_at: 1. / 1.9,
+            },
+            // the crack sound plays as the crack texture is done
+            egg_cracking: TransitionTiming {
+                duration: 3.0,
+                sound_at: 1.,
+            },
+            // the intro is silent, so its sound trigger is never used
+            intro: TransitionTiming {
+                duration: 1.,
+                sound_at: 1.,
+            },
+            // quicker than a crossfade, since both creatures are on screen the whole time,
+            // with the sound where the two are about even
+            morph: TransitionTiming {
+                duration: 6.,
+                sound_at: 0.5,
+            },
+            morphs: Vec::new(),
+            crossfade_overlap: 0.,
+            durations: [None; WORLD_STATE_VARIANTS],
+            returns: [ReturnTransition::default(); WORLD_STATE_VARIANTS],
+        }
+    }
+}
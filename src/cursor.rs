@@ -0,0 +1,54 @@
+//! A textured cursor replacing the system one, for the kiosk look.
+
+use macroquad::prelude::*;
+
+use crate::ASSET_PATH;
+
+/// how big the cursor is drawn, in world units
+const CURSOR_SIZE: f32 = 160.;
+/// how much bigger it gets above a button that can be clicked
+const HOVER_SCALE: f32 = 1.35;
+const HOVER_TINT: Color = Color::new(1., 0.85, 0.4, 1.);
+
+pub struct Cursor {
+    texture: Texture2D,
+}
+
+impl Cursor {
+    /// Loads `assets/cursor.png` and hides the system cursor, unless `native` is set
+    /// or the texture is missing, in which case the system cursor stays.
+    pub async fn load(native: bool) -> Option<Cursor> {
+        if native {
+            return None;
+        }
+        let path = format!("{}cursor.png", ASSET_PATH);
+        let texture = match load_texture(&path).await {
+            Ok(texture) => texture,
+            Err(e) => {
+                log_warn!("keeping the system cursor, couldn't load {}: {}", path, e);
+                return None;
+            }
+        };
+        show_mouse(false);
+        Some(Cursor { texture })
+    }
+
+    /// draws the cursor centered on `pos`, bigger and tinted while `hovering` a button
+    pub fn draw(&self, pos: Vec2, hovering: bool) {
+        let (size, color) = if hovering {
+            (CURSOR_SIZE * HOVER_SCALE, HOVER_TINT)
+        } else {
+            (CURSOR_SIZE, WHITE)
+        };
+        draw_texture_ex(
+            &self.texture,
+            pos.x - size / 2.,
+            pos.y - size / 2.,
+            color,
+            DrawTextureParams {
+                dest_size: Some(vec2(size, size)),
+                ..Default::default()
+            },
+        );
+    }
+}
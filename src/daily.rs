@@ -0,0 +1,103 @@
+//! The creature of the day with `--creature-of-the-day`: a leaf creature picked from the date alone,
+//! so that everyone playing on the same (UTC) day is sent looking for the same one.
+
+use macroquad::prelude::*;
+
+use crate::{graph::StateGraph, rng::Rng, strings, World, WorldState, WORLD_WIDTH};
+
+/// the banner's text, `{creature}` is replaced with the creature's name
+const BANNER_KEY: &str = "ui.creature-of-the-day";
+const BANNER_SIZE: f32 = 110.;
+const SECS_PER_DAY: u64 = 86_400;
+
+/// The leaf creature for the day `unix_secs` falls on, None if the graph has no leaves at all.
+pub fn creature_of_the_day(graph: &StateGraph, unix_secs: u64) -> Option<WorldState> {
+    let leaves: Vec<WorldState> = WorldState::all()
+        .filter(|&state| graph.is_leaf(state))
+        .collect();
+    if leaves.is_empty() {
+        return None;
+    }
+    let mut rng = Rng::new(unix_secs / SECS_PER_DAY);
+    Some(leaves[rng.next_u32() as usize % leaves.len()])
+}
+
+pub struct CreatureOfTheDay {
+    pub creature: WorldState,
+    /// reached since the game started, which ends the banner
+    found: bool,
+}
+
+impl CreatureOfTheDay {
+    /// the one for today, None if there's no leaf to pick
+    pub fn today(graph: &StateGraph) -> Option<Self> {
+        let now = macroquad::miniquad::date::now() as u64;
+        let creature = creature_of_the_day(graph, now)?;
+        log_info!("the creature of the day is {:?}", creature);
+        Some(Self {
+            creature,
+            found: false,
+        })
+    }
+
+    /// Returns whether `state` is the creature of the day, reached for the first time today.
+    pub fn on_reached(&mut self, state: WorldState) -> bool {
+        let just_found = state == self.creature && !self.found;
+        self.found |= just_found;
+        just_found
+    }
+
+    /// the prompt to go and find it, across the top, until it's found
+    pub fn draw(&self, world: &World) {
+        if self.found {
+            return;
+        }
+        let Some(text) = world.strings.get(BANNER_KEY) else {
+            return;
+        };
+        let text = text.replace("{creature}", self.creature.display_name());
+        let font = world.font.as_ref();
+        let dims = measure_text(&text, font, BANNER_SIZE as u16, 1.);
+        let padding = 50.;
+        let y = 300.;
+        draw_rectangle(
+            (WORLD_WIDTH - dims.width) / 2. - padding,
+            y,
+            dims.width + 2. * padding,
+            dims.height + 2. * padding,
+            Color::new(0., 0., 0., 0.6),
+        );
+        strings::draw_text_in(
+            font,
+            &text,
+            (WORLD_WIDTH - dims.width) / 2.,
+            y + padding + dims.offset_y,
+            BANNER_SIZE,
+            GOLD,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_creature_only_changes_with_the_day() {
+        let graph = StateGraph::default();
+        let morning = 20_000 * SECS_PER_DAY + 3_600;
+        let pick = creature_of_the_day(&graph, morning).unwrap();
+        assert!(graph.is_leaf(pick));
+        assert_eq!(
+            creature_of_the_day(&graph, morning + 20 * 3_600),
+            Some(pick)
+        );
+        // over a month every leaf comes up at some point
+        let picks: Vec<_> = (0..30)
+            .filter_map(|day| creature_of_the_day(&graph, (20_000 + day) * SECS_PER_DAY))
+            .collect();
+        assert!(WorldState::all()
+            .filter(|&state| graph.is_leaf(state))
+            .all(|leaf| picks.contains(&leaf)));
+    }
+}
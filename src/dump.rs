@@ -0,0 +1,116 @@
+//! A snapshot of everything the game is doing right now, written as JSON for bug reports.
+//!
+//! Dumps go through [`crate::persistence`] under `dumps/<timestamp>.json` like the session logs,
+//! so into files on native and into the browser's localStorage on the web.
+
+use crate::{persistence, Button, Transition, World, WorldState};
+
+const DUMPS_DIR: &str = "dumps/";
+
+/// `"Name"`, or `null` for None
+fn json_name(name: Option<impl std::fmt::Debug>) -> String {
+    match name {
+        Some(name) => format!("\"{:?}\"", name),
+        None => "null".to_string(),
+    }
+}
+
+/// a JSON object of one number per state, leaving out the ones for which `value` is None
+fn json_per_state(value: impl Fn(WorldState) -> Option<String>) -> String {
+    let fields: Vec<String> = WorldState::ALL
+        .into_iter()
+        .filter_map(|state| Some(format!("\"{:?}\": {}", state, value(state)?)))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+impl Transition {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"goal\": \"{:?}\", \"type\": \"{:?}\", \"progressed\": {:.3}, \"duration\": {:.3}, \"from_image\": {}}}",
+            self.goal_state,
+            self.t_type,
+            self.time_progressed,
+            self.total_duration(),
+            json_name(self.from_image)
+        )
+    }
+}
+
+impl Button {
+    fn to_json(&self, enabled: bool, greyed_out: bool) -> String {
+        format!(
+            "{{\"type\": \"{:?}\", \"state\": \"{:?}\", \"enabled\": {}, \"greyed_out\": {}, \"visibility\": {:.3}, \"hover_time\": {:.3}}}",
+            self.b_type, self.state, enabled, greyed_out, self.visibility, self.hover_time
+        )
+    }
+}
+
+impl World {
+    fn to_json(&self) -> String {
+        let transition = match self.sim.transition {
+            Some(ref t) => t.to_json(),
+            None => "null".to_string(),
+        };
+        let queue: Vec<String> = self.sim.queued().map(|s| format!("\"{:?}\"", s)).collect();
+        let buttons: Vec<String> = self
+            .buttons
+            .iter()
+            .map(|button| {
+                let b_type = button.b_type;
+                let json = button.to_json(self.sim.is_enabled(b_type), self.greyed_out(b_type));
+                format!("    {}", json)
+            })
+            .collect();
+        let discovered = json_per_state(|state| {
+            self.discovered.contains(state).then(|| {
+                self.discovered
+                    .first_discovered(state)
+                    .unwrap_or(0)
+                    .to_string()
+            })
+        });
+        let reached = json_per_state(|state| Some(self.stats.reached[state as usize].to_string()));
+        format!(
+            concat!(
+                "{{\n",
+                "  \"time\": {:.0},\n",
+                "  \"state\": \"{:?}\",\n",
+                "  \"transition\": {},\n",
+                "  \"queue\": [{}],\n",
+                "  \"idle_time\": {:.3},\n",
+                "  \"paused\": {},\n",
+                "  \"buttons\": [\n{}\n  ],\n",
+                "  \"settings\": {{\"reduced_motion\": {}, \"muted\": {}, \"volume\": {:.2}, \"interruptible\": {}, \"unwind\": {}, \"transition_speed\": {:.2}}},\n",
+                "  \"discovered\": {},\n",
+                "  \"stats\": {{\"transitions\": {}, \"reached\": {}}}\n",
+                "}}\n"
+            ),
+            macroquad::miniquad::date::now(),
+            self.sim.state,
+            transition,
+            queue.join(", "),
+            self.idle_time,
+            self.paused,
+            buttons.join(",\n"),
+            self.preferences.reduced_motion,
+            self.config.mute,
+            self.config.volume,
+            self.config.interruptible,
+            self.config.unwind,
+            self.config.transition_speed,
+            discovered,
+            self.stats.transitions,
+            reached,
+        )
+    }
+
+    /// Writes everything worth knowing about the current frame into `DUMPS_DIR`, to attach to a bug report.
+    pub fn write_dump(&self) {
+        let key = format!("{}{:.0}.json", DUMPS_DIR, macroquad::miniquad::date::now());
+        match persistence::set(&key, &self.to_json()) {
+            Ok(()) => log_info!("dumped the runtime state to {}", key),
+            Err(e) => log_error!("{}", e),
+        }
+    }
+}
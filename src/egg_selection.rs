@@ -0,0 +1,71 @@
+//! The screen at the start letting the player pick which egg to begin with, with `--choose-egg`.
+
+use macroquad::prelude::*;
+
+use crate::{strings, World, WorldState, WORLD_HEIGHT, WORLD_WIDTH};
+
+const EGGS: [WorldState; 2] = [WorldState::Egg, WorldState::BigEgg];
+/// each egg's whole canvas is shown at this fraction of the world
+const EGG_SCALE: f32 = 0.46;
+const LABEL_SIZE: f32 = 160.;
+
+/// where the canvas of the egg at `index` in `EGGS` is drawn, side by side in the middle of the world
+fn egg_rect(index: usize) -> Rect {
+    let (w, h) = (WORLD_WIDTH * EGG_SCALE, WORLD_HEIGHT * EGG_SCALE);
+    let gap = (WORLD_WIDTH - 2. * w) / 3.;
+    Rect::new(
+        gap + index as f32 * (w + gap),
+        (WORLD_HEIGHT - h) / 2.,
+        w,
+        h,
+    )
+}
+
+/// Shows both eggs until one of them is clicked (or picked with the arrow keys and Enter), nothing else reacts.
+/// None if the window is closed instead.
+pub async fn select_egg(cam: &Camera2D, world: &World) -> Option<WorldState> {
+    let mut highlighted = 0;
+    loop {
+        if crate::quit_requested() {
+            return None;
+        }
+        clear_background(Color::default());
+        set_camera(cam);
+
+        if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Right) {
+            highlighted = 1 - highlighted;
+        }
+        let mouse_pos = crate::mouse_world_pos(cam);
+        let hovered = (0..EGGS.len()).find(|&i| egg_rect(i).contains(mouse_pos));
+        if let Some(i) = hovered {
+            highlighted = i;
+        }
+        let chosen = is_key_pressed(KeyCode::Enter)
+            || (hovered.is_some() && is_mouse_button_pressed(MouseButton::Left));
+        if chosen {
+            return Some(EGGS[highlighted]);
+        }
+
+        for (i, egg) in EGGS.into_iter().enumerate() {
+            let rect = egg_rect(i);
+            let color = if i == highlighted {
+                WHITE
+            } else {
+                Color::new(0.6, 0.6, 0.6, 1.)
+            };
+            world.draw_state_at(egg, rect, color);
+            strings::draw_text_in(
+                world.font.as_ref(),
+                egg.display_name(),
+                rect.x + 60.,
+                rect.bottom() + LABEL_SIZE,
+                LABEL_SIZE,
+                color,
+            );
+        }
+        if let Some(ref daily) = world.daily {
+            daily.draw(world);
+        }
+        next_frame().await;
+    }
+}
@@ -0,0 +1,68 @@
+//! What can go wrong outside of the game's own logic, from loading assets to saving, in one type,
+//! so that every failure is reported the same way and the ones that end the game do so uniformly.
+
+use std::fmt;
+
+use crate::WorldState;
+
+#[derive(Debug)]
+pub enum GameError {
+    /// a file that couldn't be loaded, or that doesn't make sense once it is, e.g. a script
+    Asset { path: String, message: String },
+    /// a command line argument that doesn't parse, which is ignored
+    Config { arg: String, message: String },
+    /// the persistence key that couldn't be written
+    Save { key: String, message: String },
+    /// a state graph that can't be played, e.g. because some states can't be reached
+    Graph(String),
+    /// a sound that couldn't be loaded, which then just stays silent
+    Audio { file: String, message: String },
+}
+
+impl GameError {
+    /// the error for a graph in which `orphans` can't be reached from any egg
+    pub fn orphans(orphans: &[WorldState]) -> Self {
+        GameError::Graph(format!(
+            "these states can't be reached from any egg: {:?}",
+            orphans
+        ))
+    }
+
+    /// what the process exits with because of this, different per kind, so that scripts can tell them apart
+    #[cfg(not(target_arch = "wasm32"))]
+    fn exit_code(&self) -> i32 {
+        match self {
+            GameError::Asset { .. } => 2,
+            GameError::Config { .. } => 3,
+            GameError::Save { .. } => 4,
+            GameError::Graph(_) => 5,
+            GameError::Audio { .. } => 6,
+        }
+    }
+
+    /// Logs the error and ends the game with its exit code. There is no exit code on the web,
+    /// so it panics there, which stops the game all the same.
+    pub fn exit(self) -> ! {
+        log_error!("{}", self);
+        #[cfg(target_arch = "wasm32")]
+        panic!("{}", self);
+        #[cfg(not(target_arch = "wasm32"))]
+        std::process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Asset { path, message } => write!(f, "couldn't load {}: {}", path, message),
+            GameError::Config { arg, message } => write!(f, "argument {}: {}", arg, message),
+            GameError::Save { key, message } => write!(f, "couldn't save {}: {}", key, message),
+            GameError::Graph(message) => write!(f, "{}", message),
+            GameError::Audio { file, message } => {
+                write!(f, "couldn't load the sound {}: {}", file, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
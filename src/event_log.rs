@@ -0,0 +1,90 @@
+//! The last few things that happened, shown in-game with F11 (unless rebound), for looking into a kiosk
+//! that has no console attached. Everything logged at `info` or above ends up in here, and so does every
+//! click and every state reached.
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::{World, WORLD_WIDTH};
+
+/// how many entries are kept, the oldest one is dropped for each one beyond that
+pub const CAPACITY: usize = 20;
+const FONT_SIZE: f32 = 56.;
+const LINE_HEIGHT: f32 = 70.;
+
+#[derive(Default)]
+pub struct EventLog {
+    /// `get_time()` of each entry and what happened, the oldest first
+    entries: VecDeque<(f64, String)>,
+    pub shown: bool,
+}
+
+impl EventLog {
+    pub fn push(&mut self, time: f64, message: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((time, message));
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &(f64, String)> {
+        self.entries.iter()
+    }
+}
+
+impl World {
+    /// adds `message` to the event log, at the current time
+    pub fn record_event(&mut self, message: String) {
+        self.event_log.push(get_time(), message);
+    }
+
+    /// Takes over what has been logged since the last frame. The log only hands the lines over here,
+    /// since it can't know the time before the window exists.
+    pub fn update_event_log(&mut self) {
+        for line in crate::logging::take_recent() {
+            self.record_event(line);
+        }
+    }
+
+    /// the entries at the top of the world, the newest at the bottom, if the log is shown
+    pub fn draw_event_log(&self) {
+        if !self.event_log.shown {
+            return;
+        }
+        let lines = self.event_log.entries.len();
+        draw_rectangle(
+            0.,
+            0.,
+            WORLD_WIDTH,
+            LINE_HEIGHT * (lines as f32 + 1.),
+            Color::new(0., 0., 0., 0.7),
+        );
+        for (i, (time, message)) in self.event_log.entries().enumerate() {
+            draw_text(
+                &format!("{:8.2}  {}", time, message),
+                40.,
+                LINE_HEIGHT * (i as f32 + 1.),
+                FONT_SIZE,
+                WHITE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_newest_entries_are_kept() {
+        let mut log = EventLog::default();
+        for i in 0..CAPACITY + 5 {
+            log.push(i as f64, format!("event {}", i));
+        }
+        let entries: Vec<_> = log.entries().collect();
+        assert_eq!(entries.len(), CAPACITY);
+        assert_eq!(*entries[0], (5., "event 5".to_string()));
+        assert_eq!(entries[CAPACITY - 1].1, format!("event {}", CAPACITY + 4));
+    }
+}
@@ -0,0 +1,260 @@
+//! Offline rendering of the world into textures that are written out as PNGs.
+
+use macroquad::prelude::*;
+
+use crate::{error::GameError, ButtonType, World, WorldState, WORLD_HEIGHT, WORLD_WIDTH};
+
+/// exported frames are a quarter of the world size, which is plenty for a GIF or video
+const EXPORT_SCALE: f32 = 0.25;
+/// the simulation is stepped with this delta for every exported frame, regardless of the real frame rate
+const EXPORT_FRAME_DELTA: f32 = 1. / 30.;
+const EXPORT_DIR: &str = "export/";
+/// layout of the contact sheet, which needs to fit every state
+const SHEET_COLUMNS: usize = 5;
+const SHEET_CELL_WIDTH: f32 = 496.;
+const SHEET_CELL_HEIGHT: f32 = SHEET_CELL_WIDTH * WORLD_HEIGHT / WORLD_WIDTH;
+const SHEET_LABEL_HEIGHT: f32 = 70.;
+
+/// A camera showing the whole world, drawing into `target` instead of the screen.
+fn render_target_camera(target: &RenderTarget) -> Camera2D {
+    render_target_camera_for(target, Rect::new(0., 0., WORLD_WIDTH, WORLD_HEIGHT))
+}
+
+/// A camera showing `rect`, drawing into `target` instead of the screen.
+fn render_target_camera_for(target: &RenderTarget, rect: Rect) -> Camera2D {
+    // no y-flip needed here, unlike the camera for the screen in main
+    let mut cam = Camera2D::from_display_rect(rect);
+    cam.render_target = Some(target.clone());
+    cam
+}
+
+/// Writes the contents of a texture to `path`, e.g. after it has been rendered to.
+pub fn save_texture_png(texture: &Texture2D, path: &str) {
+    texture.get_texture_data().export_png(path);
+}
+
+impl World {
+    /// Renders the current frame into `target` instead of the screen.
+    ///
+    /// Leaves the default camera set, so the caller has to set its own camera again afterwards.
+    pub fn render_frame_to(&self, target: &RenderTarget) {
+        set_camera(&render_target_camera(target));
+        clear_background(Color::default());
+        self.render();
+        // flushes the draw calls into the target
+        set_default_camera();
+    }
+
+    /// Renders only `state` into `target`, without any transition, buttons or effects on top.
+    #[cfg(all(test, feature = "render-tests"))]
+    pub fn render_state_to(&self, state: WorldState, target: &RenderTarget) {
+        set_camera(&render_target_camera(target));
+        clear_background(Color::default());
+        if self.config.shadows {
+            self.draw_state_shadow(state, 1.);
+        }
+        self.draw_state(state, WHITE);
+        set_default_camera();
+    }
+}
+
+/// Creates `EXPORT_DIR` if it isn't there yet, ending the game with an error if it can't.
+fn create_export_dir() {
+    if let Err(e) = std::fs::create_dir_all(EXPORT_DIR) {
+        GameError::Save {
+            key: EXPORT_DIR.to_string(),
+            message: e.to_string(),
+        }
+        .exit();
+    }
+}
+
+/// Starts the transition `b_type` leads to from `from` and writes every frame of it into `EXPORT_DIR`,
+/// stepping the simulation at a fixed delta, until the world comes to rest again.
+/// Ends the game with an error if `b_type` doesn't lead anywhere from `from`.
+pub fn export_transition(world: &mut World, from: WorldState, b_type: ButtonType) {
+    world.sim.jump_to(from);
+    if world.sim.goal_for(b_type).is_none() {
+        GameError::Config {
+            arg: "--export-transition".to_string(),
+            message: format!("{:?} doesn't lead anywhere from {:?}", b_type, from),
+        }
+        .exit();
+    }
+    create_export_dir();
+    let target = render_target(
+        (WORLD_WIDTH * EXPORT_SCALE) as u32,
+        (WORLD_HEIGHT * EXPORT_SCALE) as u32,
+    );
+
+    world.sim.start_transition(b_type);
+
+    let mut frame = 0;
+    loop {
+        world.render_frame_to(&target);
+        save_texture_png(
+            &target.texture,
+            format!("{}frame_{:04}.png", EXPORT_DIR, frame).as_str(),
+        );
+        frame += 1;
+        if world.sim.transition.is_none() {
+            break;
+        }
+        world.progress(EXPORT_FRAME_DELTA);
+    }
+    println!("exported {} frames into {}", frame, EXPORT_DIR);
+}
+
+/// Times rendering `frames` frames at rest and as many in the middle of a transition, both into a
+/// full-size render target, and prints the average per frame. This measures what `render` costs on the
+/// CPU, queuing up the draw calls, the GPU catches up on those in its own time.
+pub fn bench_render(world: &mut World, frames: usize) {
+    let target = render_target(WORLD_WIDTH as u32, WORLD_HEIGHT as u32);
+    let time_frames = |world: &World| {
+        let start = get_time();
+        for _ in 0..frames {
+            world.render_frame_to(&target);
+        }
+        (get_time() - start) * 1000. / frames as f64
+    };
+
+    world.sim.jump_to(WorldState::Chick);
+    let at_rest = time_frames(world);
+    world.sim.start_transition(ButtonType::Water);
+    let half = world
+        .sim
+        .transition
+        .as_ref()
+        .map_or(0., |t| t.total_duration() / 2.);
+    world.sim.progress(half);
+    let in_transition = time_frames(world);
+    println!(
+        "rendering {} frames each: {:.3} ms per frame at rest, {:.3} ms during a transition",
+        frames, at_rest, in_transition
+    );
+}
+
+/// Renders every state's texture into one grid, each labeled with its index and name, and writes it
+/// into `EXPORT_DIR`. Wrongly sized or duplicated assets stand out immediately on it.
+pub fn export_contact_sheet(world: &World) {
+    create_export_dir();
+    let rows = WorldState::all().count().div_ceil(SHEET_COLUMNS);
+    let row_height = SHEET_CELL_HEIGHT + SHEET_LABEL_HEIGHT;
+    let width = SHEET_COLUMNS as f32 * SHEET_CELL_WIDTH;
+    let height = rows as f32 * row_height;
+    let target = render_target(width as u32, height as u32);
+
+    set_camera(&render_target_camera_for(
+        &target,
+        Rect::new(0., 0., width, height),
+    ));
+    clear_background(Color::new(0.15, 0.15, 0.15, 1.));
+    for (i, state) in WorldState::all().enumerate() {
+        let x = (i % SHEET_COLUMNS) as f32 * SHEET_CELL_WIDTH;
+        let y = (i / SHEET_COLUMNS) as f32 * row_height;
+        // a dark cell behind each texture shows how much of it is transparent
+        draw_rectangle(x, y, SHEET_CELL_WIDTH, SHEET_CELL_HEIGHT, BLACK);
+        match world.state_image(state) {
+            Ok(image) => image.draw(Rect::new(x, y, SHEET_CELL_WIDTH, SHEET_CELL_HEIGHT), WHITE),
+            Err(msg) => log_error!("{}", msg),
+        }
+        draw_text(
+            format!("{}: {}", i, state.display_name()).as_str(),
+            x + 12.,
+            y + SHEET_CELL_HEIGHT + SHEET_LABEL_HEIGHT * 0.7,
+            SHEET_LABEL_HEIGHT * 0.7,
+            WHITE,
+        );
+    }
+    set_default_camera();
+
+    let path = format!("{}contact_sheet.png", EXPORT_DIR);
+    save_texture_png(&target.texture, &path);
+    println!("exported the contact sheet to {}", path);
+}
+
+/// Renders every state and compares it against a reference PNG in `tests/golden/states/`, to catch
+/// accidental changes to the assets or the rendering. These need a window, so they only run with
+/// `cargo test --features render-tests`, and `UPDATE_GOLDEN=1` (re)writes the references. A state without
+/// a reference yet is skipped, and reported as such, rather than failing on a fresh checkout.
+#[cfg(all(test, feature = "render-tests"))]
+mod render_tests {
+    use super::*;
+    use crate::config::Config;
+
+    const REFERENCE_DIR: &str = "tests/golden/states/";
+    const DIFF_DIR: &str = "target/golden-diff/";
+    /// small, the point is catching a wrong or shifted image, not single pixels
+    const WIDTH: u32 = 124;
+    const HEIGHT: u32 = 175;
+    /// how far the channels may be off on average, out of 255, before it counts as a mismatch
+    const TOLERANCE: f32 = 2.;
+
+    /// the average absolute difference over all channels, and an image showing where they differ
+    fn compare(live: &Image, reference: &Image) -> (f32, Image) {
+        let mut diff = Image::gen_image_color(live.width, live.height, BLACK);
+        let mut total = 0.;
+        for y in 0..live.height as u32 {
+            for x in 0..live.width as u32 {
+                let (a, b) = (live.get_pixel(x, y), reference.get_pixel(x, y));
+                let d = [a.r - b.r, a.g - b.g, a.b - b.b, a.a - b.a].map(f32::abs);
+                total += d.iter().sum::<f32>();
+                let strongest = d.into_iter().fold(0., f32::max);
+                diff.set_pixel(x, y, Color::new(strongest, strongest, strongest, 1.));
+            }
+        }
+        let channels = live.width as f32 * live.height as f32 * 4.;
+        (total / channels * 255., diff)
+    }
+
+    #[macroquad::test]
+    async fn every_state_renders_like_its_reference() {
+        let world = World::new(Config::default(), None).await;
+        let target = render_target(WIDTH, HEIGHT);
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut mismatches = Vec::new();
+        let mut skipped = Vec::new();
+        for state in WorldState::all() {
+            world.render_state_to(state, &target);
+            let live = target.texture.get_texture_data();
+            let path = format!("{}{}.png", REFERENCE_DIR, state as usize);
+            if update {
+                std::fs::create_dir_all(REFERENCE_DIR).unwrap();
+                live.export_png(&path);
+                continue;
+            }
+            if !std::path::Path::new(&path).exists() {
+                skipped.push(state);
+                continue;
+            }
+            let reference = load_image(&path)
+                .await
+                .unwrap_or_else(|e| panic!("couldn't load {}: {}", path, e));
+            assert_eq!(
+                (reference.width, reference.height),
+                (live.width, live.height),
+                "{} has the wrong size",
+                path
+            );
+            let (difference, diff) = compare(&live, &reference);
+            if difference > TOLERANCE {
+                std::fs::create_dir_all(DIFF_DIR).unwrap();
+                let diff_path = format!("{}{}.png", DIFF_DIR, state as usize);
+                diff.export_png(&diff_path);
+                mismatches.push(format!(
+                    "{:?} is off by {:.2} on average, see {}",
+                    state, difference, diff_path
+                ));
+            }
+        }
+        if !skipped.is_empty() {
+            eprintln!(
+                "skipped {} states without a reference in {}, run with UPDATE_GOLDEN=1 to create them: {:?}",
+                skipped.len(),
+                REFERENCE_DIR,
+                skipped
+            );
+        }
+        assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Every creature at a glance, the undiscovered ones as silhouettes, with a card about each one
+//! that has been discovered when it's clicked.
+
+use macroquad::prelude::*;
+
+use crate::{bindings::Action, strings, StateImage, World, WorldState, WORLD_HEIGHT, WORLD_WIDTH};
+
+const COLUMNS: usize = 5;
+const CELL_WIDTH: f32 = 440.;
+const CELL_HEIGHT: f32 = CELL_WIDTH * WORLD_HEIGHT / WORLD_WIDTH;
+const LABEL_HEIGHT: f32 = 80.;
+const FIRST_ROW_Y: f32 = 400.;
+const CARD: Rect = Rect {
+    x: 200.,
+    y: 500.,
+    w: WORLD_WIDTH - 400.,
+    h: WORLD_HEIGHT - 1000.,
+};
+const CARD_TEXT_SIZE: f32 = 90.;
+
+/// everything but the egg cracks, which aren't anything to collect
+fn shown_states(world: &World) -> Vec<WorldState> {
+    WorldState::ALL
+        .into_iter()
+        .filter(|&state| !world.sim.graph.is_crack(state))
+        .collect()
+}
+
+fn cell_rect(index: usize) -> Rect {
+    let gap = (WORLD_WIDTH - COLUMNS as f32 * CELL_WIDTH) / (COLUMNS + 1) as f32;
+    let (column, row) = (index % COLUMNS, index / COLUMNS);
+    Rect::new(
+        gap + column as f32 * (CELL_WIDTH + gap),
+        FIRST_ROW_Y + row as f32 * (CELL_HEIGHT + LABEL_HEIGHT + 40.),
+        CELL_WIDTH,
+        CELL_HEIGHT,
+    )
+}
+
+/// Shows the gallery until it's closed with its key again, or Escape, or the window is closed.
+/// While a card is open, any click or key closes just the card.
+pub async fn gallery(cam: &Camera2D, world: &World) {
+    let states = shown_states(world);
+    // decoded once for as long as the gallery is open, however few the texture cache holds
+    let images: Vec<_> = states
+        .iter()
+        .map(|&state| world.pinned_state_image(state))
+        .collect();
+    let mut card: Option<WorldState> = None;
+    // the key that opened the gallery is still pressed in this frame
+    next_frame().await;
+    loop {
+        if crate::quit_requested() {
+            return;
+        }
+        clear_background(world.config.clear_color);
+        set_camera(cam);
+
+        let clicked = is_mouse_button_pressed(MouseButton::Left);
+        if card.is_some() {
+            if clicked || get_last_key_pressed().is_some() {
+                card = None;
+            }
+        } else {
+            if is_key_pressed(KeyCode::Escape) || world.bindings.pressed(Action::Gallery) {
+                return;
+            }
+            let mouse_pos = crate::mouse_world_pos(cam);
+            let hovered = (0..states.len()).find(|&i| cell_rect(i).contains(mouse_pos));
+            if let Some(i) = hovered.filter(|_| clicked) {
+                card = Some(states[i]).filter(|&state| world.discovered.contains(state));
+            }
+        }
+
+        draw_grid(world, &states, &images);
+        if let Some(i) = card.and_then(|state| states.iter().position(|&s| s == state)) {
+            draw_card(world, states[i], &images[i]);
+        }
+        next_frame().await;
+    }
+}
+
+fn draw_image(state: WorldState, image: &Result<StateImage, String>, dest: Rect, color: Color) {
+    match image {
+        Ok(image) => image.draw(dest, color),
+        Err(_) => crate::draw_placeholder(state, color.a),
+    }
+}
+
+fn draw_grid(world: &World, states: &[WorldState], images: &[Result<StateImage, String>]) {
+    let font = world.font.as_ref();
+    let discovered = states
+        .iter()
+        .filter(|&&state| world.discovered.contains(state))
+        .count();
+    strings::draw_text_in(
+        font,
+        &format!("Gallery: {}/{}", discovered, states.len()),
+        200.,
+        250.,
+        160.,
+        WHITE,
+    );
+    for (i, &state) in states.iter().enumerate() {
+        let rect = cell_rect(i);
+        let (color, name) = if world.discovered.contains(state) {
+            (WHITE, state.display_name())
+        } else {
+            (Color::new(0.1, 0.1, 0.1, 1.), "???")
+        };
+        draw_image(state, &images[i], rect, color);
+        strings::draw_text_in(
+            font,
+            name,
+            rect.x,
+            rect.bottom() + LABEL_HEIGHT * 0.8,
+            LABEL_HEIGHT * 0.8,
+            WHITE,
+        );
+    }
+}
+
+/// name, description, where it's reached from and leads to, how often it's been reached and since when
+fn draw_card(world: &World, state: WorldState, image_of_state: &Result<StateImage, String>) {
+    let font = world.font.as_ref();
+    let graph = &world.sim.graph;
+    draw_rectangle(
+        CARD.x,
+        CARD.y,
+        CARD.w,
+        CARD.h,
+        Color::new(0.05, 0.05, 0.05, 0.95),
+    );
+    draw_rectangle_lines(CARD.x, CARD.y, CARD.w, CARD.h, 8., WHITE);
+    let padding = 80.;
+    let image_width = CARD.w * 0.4;
+    let image = Rect::new(
+        CARD.right() - padding - image_width,
+        CARD.y + padding,
+        image_width,
+        image_width * WORLD_HEIGHT / WORLD_WIDTH,
+    );
+    draw_image(state, image_of_state, image, WHITE);
+    strings::draw_text_in(
+        font,
+        state.display_name(),
+        CARD.x + padding,
+        CARD.y + padding + 120.,
+        160.,
+        WHITE,
+    );
+
+    let text_width = image.x - CARD.x - 2. * padding;
+    let mut lines = Vec::new();
+    if let Some(text) = state
+        .description_key()
+        .and_then(|key| world.strings.get(&key))
+    {
+        lines.extend(strings::wrap(text, font, CARD_TEXT_SIZE as u16, text_width));
+        lines.push(String::new());
+    }
+    let ways = |list: Vec<String>| {
+        if list.is_empty() {
+            "nowhere".to_string()
+        } else {
+            list.join(", ")
+        }
+    };
+    let from = graph
+        .ways_into(state)
+        .into_iter()
+        .map(|(from, b_type)| format!("{} ({:?})", from.display_name(), b_type))
+        .collect();
+    let to = crate::ButtonType::ALL
+        .into_iter()
+        .filter_map(|b_type| {
+            let to = graph.peek(state, b_type)?;
+            Some(format!("{} ({:?})", to.display_name(), b_type))
+        })
+        .collect();
+    for line in [format!("From: {}", ways(from)), format!("To: {}", ways(to))] {
+        lines.extend(strings::wrap(
+            &line,
+            font,
+            CARD_TEXT_SIZE as u16,
+            text_width,
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "Reached {} times",
+        world.stats.reached[state as usize]
+    ));
+    if let Some(time) = world.discovered.first_discovered(state) {
+        lines.push(format!("First discovered on {}", date(time)));
+    }
+
+    let line_height = CARD_TEXT_SIZE * 1.25;
+    let top = CARD.y + padding + 320.;
+    for (i, line) in lines.iter().enumerate() {
+        strings::draw_text_in(
+            font,
+            line,
+            CARD.x + padding,
+            top + i as f32 * line_height,
+            CARD_TEXT_SIZE,
+            WHITE,
+        );
+    }
+}
+
+/// the UTC date of a time in seconds since the Unix epoch, as year-month-day
+fn date(unix_secs: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
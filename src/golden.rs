@@ -0,0 +1,105 @@
+//! Replaying a fixed script of clicks and waits through the simulation and comparing every frame
+//! against a golden file, to make sure refactors of the transition code don't change behavior.
+//!
+//! Run the tests with `UPDATE_GOLDEN=1` to (re)write the golden files from the live simulation.
+
+use crate::{
+    config::TransitionConfig,
+    script::Step,
+    simulation::{Event, Simulation},
+};
+
+/// the simulation is always stepped with this delta, so that the frames line up between runs
+const FRAME_DELTA: f32 = 1. / 60.;
+const SEED: u64 = 1;
+
+/// one line per frame, waits are stepped through one frame at a time: the snapshot after the frame and the events during it
+fn run(script: &[Step]) -> Vec<String> {
+    let mut sim = Simulation::new(TransitionConfig::default(), SEED);
+    let mut frames = Vec::new();
+    for &step in script {
+        match step {
+            Step::Click(b_type) => {
+                let started = sim.click(b_type);
+                frames.push(format!("click {:?} started={}", b_type, started));
+            }
+            Step::Wait(secs) => {
+                for _ in 0..(secs / FRAME_DELTA).round() as usize {
+                    let events = sim.progress(FRAME_DELTA);
+                    let mut line = sim.snapshot().to_string();
+                    for event in events {
+                        // Reached events are visible in the snapshot already
+                        if let Event::Sound(sound) = event {
+                            line += &format!(" sound={:?}", sound);
+                        }
+                    }
+                    frames.push(line);
+                }
+            }
+        }
+    }
+    frames
+}
+
+/// Runs `script` and fails with the first frame that differs from the golden file at `golden_path`.
+pub fn assert_matches_golden(script: &[Step], golden_path: &str) {
+    let frames = run(script);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(golden_path, frames.join("\n") + "\n")
+            .expect("couldn't write the golden file");
+        return;
+    }
+
+    let golden = std::fs::read_to_string(golden_path).unwrap_or_else(|e| {
+        panic!(
+            "couldn't read {}: {} (run with UPDATE_GOLDEN=1 to create it)",
+            golden_path, e
+        )
+    });
+    let golden: Vec<&str> = golden.lines().collect();
+    for (i, frame) in frames.iter().enumerate() {
+        match golden.get(i) {
+            Some(expected) if expected == frame => {}
+            Some(expected) => panic!(
+                "frame {} diverges from {}:\n  golden: {}\n  live:   {}",
+                i, golden_path, expected, frame
+            ),
+            None => panic!("frame {} is missing from {}: {}", i, golden_path, frame),
+        }
+    }
+    assert_eq!(
+        frames.len(),
+        golden.len(),
+        "{} has more frames than the live simulation",
+        golden_path
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonType;
+
+    #[test]
+    fn egg_to_duck_and_back_matches_golden() {
+        use ButtonType::*;
+        use Step::*;
+        assert_matches_golden(
+            &[
+                Wait(0.5),
+                Click(Sun),
+                // clicks during transitions are ignored
+                Wait(2.),
+                Click(Water),
+                Wait(14.),
+                Click(Water),
+                Wait(10.),
+                Click(Arrowhead),
+                Wait(10.),
+                Click(Restart),
+                Wait(10.),
+            ],
+            "tests/golden/egg_to_duck.txt",
+        );
+    }
+}
@@ -0,0 +1,535 @@
+//! The edges of the state machine: which state a button leads to from which state.
+
+use std::collections::VecDeque;
+
+use crate::{error::GameError, ButtonType, WorldState, WORLD_STATE_VARIANTS};
+
+const BUTTON_TYPES: usize = 4;
+
+/// Egg crack states don't wait for a click, their edges are followed automatically once the crack is done,
+/// using the button that started the cracking. That's how the same crack leads to different hatchlings.
+pub struct StateGraph {
+    edges: [[Option<WorldState>; BUTTON_TYPES]; WORLD_STATE_VARIANTS],
+    /// the crack states of each egg in the order they crack, any number of them
+    crack_chains: Vec<Vec<WorldState>>,
+}
+
+impl StateGraph {
+    pub fn from_edges(edges: &[(WorldState, ButtonType, WorldState)]) -> Self {
+        let mut graph = StateGraph {
+            edges: [[None; BUTTON_TYPES]; WORLD_STATE_VARIANTS],
+            crack_chains: Vec::new(),
+        };
+        for &(from, b_type, to) in edges {
+            graph.edges[from as usize][b_type as usize] = Some(to);
+        }
+        graph
+    }
+
+    /// The graph written down in `text`, one edge per line, or an error naming the first line that isn't one.
+    /// Blank lines and everything after a `#` are ignored, see `assets/states.txt`:
+    ///
+    /// ```text
+    /// egg sun eggcrack1          # <from> <button> <to>
+    /// cracks eggcrack1 eggcrack2 # a chain of egg cracks, see with_crack_chains
+    /// ```
+    #[cfg(any(test, feature = "hot-reload"))]
+    pub fn parse(text: &str) -> Result<Self, GameError> {
+        Self::parse_lines(text).map_err(GameError::Graph)
+    }
+
+    #[cfg(any(test, feature = "hot-reload"))]
+    fn parse_lines(text: &str) -> Result<Self, String> {
+        let state = |i: usize, name: &str| {
+            WorldState::from_name(name)
+                .ok_or_else(|| format!("line {}: unknown state {}", i + 1, name))
+        };
+        let mut edges = Vec::new();
+        let mut chains = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => continue,
+                ["cracks", ref chain @ ..] if !chain.is_empty() => chains.push(
+                    chain
+                        .iter()
+                        .map(|name| state(i, name))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                [from, button, to] => {
+                    let b_type = ButtonType::from_name(button)
+                        .ok_or_else(|| format!("line {}: unknown button {}", i + 1, button))?;
+                    edges.push((state(i, from)?, b_type, state(i, to)?));
+                }
+                _ => return Err(format!("line {}: expected <from> <button> <to>", i + 1)),
+            }
+        }
+        let mut graph = StateGraph::from_edges(&edges);
+        graph.crack_chains = chains;
+        Ok(graph)
+    }
+
+    /// Marks the states of each chain as egg cracks, to be cracked through one after another.
+    /// The edges between them still have to be part of the graph.
+    pub fn with_crack_chains(mut self, chains: &[&[WorldState]]) -> Self {
+        self.crack_chains = chains.iter().map(|chain| chain.to_vec()).collect();
+        self
+    }
+
+    pub fn is_crack(&self, state: WorldState) -> bool {
+        self.crack_stage(state).is_some()
+    }
+
+    /// which stage of how many in its chain the crack `state` is, counting from 0
+    pub fn crack_stage(&self, state: WorldState) -> Option<(usize, usize)> {
+        self.crack_chains.iter().find_map(|chain| {
+            let stage = chain.iter().position(|&crack| crack == state)?;
+            Some((stage, chain.len()))
+        })
+    }
+
+    /// where `b_type` leads from `from`, if anywhere
+    pub fn next(&self, from: WorldState, b_type: ButtonType) -> Option<WorldState> {
+        self.edges[from as usize][b_type as usize]
+    }
+
+    /// Where `b_type` leads from `from` in the end, following egg cracks all the way to the hatchling.
+    pub fn peek(&self, from: WorldState, b_type: ButtonType) -> Option<WorldState> {
+        let mut state = self.next(from, b_type)?;
+        while self.is_crack(state) {
+            state = self.next(state, b_type)?;
+        }
+        Some(state)
+    }
+
+    /// whether `state` is the end of a line, where only the restart is left
+    pub fn is_leaf(&self, state: WorldState) -> bool {
+        self.next(state, ButtonType::Restart).is_some()
+    }
+
+    /// The one state leading to `state`, None if none does or several do (like for the eggs, where every
+    /// line restarts). If just one of several is an egg, that's the parent though, the way the big egg is
+    /// laid by the egg first and foremost and only later on by the bird. Other states with more than one
+    /// parent simply can't be backed out of.
+    pub fn parent(&self, state: WorldState) -> Option<WorldState> {
+        let parents: Vec<WorldState> = WorldState::ALL
+            .into_iter()
+            .filter(|&from| self.successors(from).any(|to| to == state))
+            .collect();
+        if let [parent] = parents[..] {
+            return Some(parent);
+        }
+        let mut eggs = parents
+            .into_iter()
+            .filter(|&parent| self.depth(parent) == Some(0));
+        match (eggs.next(), eggs.next()) {
+            (Some(egg), None) => Some(egg),
+            _ => None,
+        }
+    }
+
+    /// Every state and button leading into `state`, with egg cracks traced back to their egg,
+    /// since that's where the button is actually clicked.
+    pub fn ways_into(&self, state: WorldState) -> Vec<(WorldState, ButtonType)> {
+        let mut ways = Vec::new();
+        for from in WorldState::ALL {
+            for b_type in ButtonType::ALL {
+                if self.edges[from as usize][b_type as usize] != Some(state) {
+                    continue;
+                }
+                let mut from = from;
+                while self.is_crack(from) {
+                    match self.parent(from) {
+                        Some(parent) => from = parent,
+                        None => break,
+                    }
+                }
+                if !ways.contains(&(from, b_type)) {
+                    ways.push((from, b_type));
+                }
+            }
+        }
+        ways
+    }
+
+    fn successors(&self, from: WorldState) -> impl Iterator<Item = WorldState> + '_ {
+        self.edges[from as usize].iter().flatten().copied()
+    }
+
+    /// The whole graph in Graphviz DOT, nodes labeled with the creature names and edges with the buttons.
+    /// Egg cracks are dashed, since they're passed through without a click.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph creatures {\n");
+        for state in WorldState::ALL {
+            let style = if self.is_crack(state) {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            dot += &format!(
+                "    {:?} [label=\"{}\"{}];\n",
+                state,
+                state.display_name(),
+                style
+            );
+        }
+        for from in WorldState::ALL {
+            for b_type in ButtonType::ALL {
+                if let Some(to) = self.next(from, b_type) {
+                    dot += &format!("    {:?} -> {:?} [label=\"{:?}\"];\n", from, to, b_type);
+                }
+            }
+        }
+        dot + "}\n"
+    }
+
+    /// how many transitions `state` is away from the closest egg at least, None if it can't be reached
+    pub fn depth(&self, state: WorldState) -> Option<usize> {
+        let mut depths = [None; WORLD_STATE_VARIANTS];
+        let mut queue = VecDeque::from([(WorldState::Egg, 0), (WorldState::BigEgg, 0)]);
+        while let Some((from, depth)) = queue.pop_front() {
+            if depths[from as usize].is_some() {
+                continue;
+            }
+            depths[from as usize] = Some(depth);
+            queue.extend(self.successors(from).map(|to| (to, depth + 1)));
+        }
+        depths[state as usize]
+    }
+
+    /// Every state that can be reached from `from`, `from` first and then by how many transitions
+    /// it takes to get there, the states the player may see soon at the front.
+    pub fn by_distance(&self, from: WorldState) -> Vec<WorldState> {
+        let mut seen = [false; WORLD_STATE_VARIANTS];
+        let mut order = Vec::with_capacity(WORLD_STATE_VARIANTS);
+        let mut queue = VecDeque::from([from]);
+        while let Some(state) = queue.pop_front() {
+            if seen[state as usize] {
+                continue;
+            }
+            seen[state as usize] = true;
+            order.push(state);
+            queue.extend(self.successors(state));
+        }
+        order
+    }
+
+    /// The states along the shortest way from `from` to `to`, both included, None if there is none.
+    pub fn shortest_path(&self, from: WorldState, to: WorldState) -> Option<Vec<WorldState>> {
+        let mut previous: [Option<WorldState>; WORLD_STATE_VARIANTS] = [None; WORLD_STATE_VARIANTS];
+        let mut queue = VecDeque::from([from]);
+        while let Some(state) = queue.pop_front() {
+            if state == to {
+                let mut path = vec![to];
+                while let Some(p) = previous[path[path.len() - 1] as usize] {
+                    path.push(p);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.successors(state) {
+                if next != from && previous[next as usize].is_none() {
+                    previous[next as usize] = Some(state);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks that the graph can be played without getting stuck: every state can be reached from one of
+    /// the eggs, leads on somewhere (if only back to the egg), and every egg crack goes on with each button
+    /// that can crack into it, since cracking carries on with the button that started it.
+    pub fn validate(&self) -> Result<(), GameError> {
+        self.validate_reachability()
+            .map_err(|orphans| GameError::orphans(&orphans))?;
+        let dead_ends: Vec<WorldState> = WorldState::ALL
+            .into_iter()
+            .filter(|&state| self.successors(state).next().is_none())
+            .collect();
+        if !dead_ends.is_empty() {
+            return Err(GameError::Graph(format!(
+                "these states don't lead anywhere: {:?}",
+                dead_ends
+            )));
+        }
+        for from in WorldState::ALL {
+            for b_type in ButtonType::ALL {
+                let Some(crack) = self.next(from, b_type).filter(|&to| self.is_crack(to)) else {
+                    continue;
+                };
+                if self.next(crack, b_type).is_none() {
+                    return Err(GameError::Graph(format!(
+                        "{:?} cracks into {:?} from {:?}, which doesn't go on with {:?}",
+                        b_type, crack, from, b_type
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every state can be reached from one of the eggs, returning the orphans otherwise.
+    pub fn validate_reachability(&self) -> Result<(), Vec<WorldState>> {
+        let mut reached = [false; WORLD_STATE_VARIANTS];
+        let mut queue = VecDeque::from([WorldState::Egg, WorldState::BigEgg]);
+        while let Some(state) = queue.pop_front() {
+            if reached[state as usize] {
+                continue;
+            }
+            reached[state as usize] = true;
+            queue.extend(self.successors(state));
+        }
+
+        let orphans: Vec<WorldState> = WorldState::ALL
+            .into_iter()
+            .filter(|&state| !reached[state as usize])
+            .collect();
+        if orphans.is_empty() {
+            Ok(())
+        } else {
+            Err(orphans)
+        }
+    }
+}
+
+impl Default for StateGraph {
+    fn default() -> Self {
+        use ButtonType::*;
+        use WorldState::*;
+        StateGraph::from_edges(&[
+            (Egg, Sun, EggCrack1),
+            (Egg, Water, EggCrack1),
+            (Egg, Arrowhead, BigEgg),
+            (EggCrack1, Sun, EggCrack2),
+            (EggCrack1, Water, EggCrack2),
+            (EggCrack2, Sun, Chick),
+            (EggCrack2, Water, BabyTurtle),
+            (Chick, Water, Duckling),
+            (Chick, Arrowhead, Bird),
+            (Duckling, Arrowhead, Duck),
+            (Duck, Restart, Egg),
+            (Bird, Water, Heron),
+            // the second way into the big egg's line, so that it isn't only reachable from the start
+            (Bird, Sun, BigEgg),
+            (Heron, Restart, Egg),
+            (BabyTurtle, Sun, Salamander),
+            (BabyTurtle, Arrowhead, Turtle),
+            (Salamander, Arrowhead, Dragonmander),
+            (Dragonmander, Restart, Egg),
+            (Turtle, Sun, TurtleWizard),
+            (TurtleWizard, Restart, Egg),
+            (BigEgg, Sun, BigEggCrack1),
+            (BigEgg, Water, BigEggCrack1),
+            (BigEggCrack1, Sun, BigEggCrack2),
+            (BigEggCrack1, Water, BigEggCrack2),
+            (BigEggCrack2, Sun, SmallDragon),
+            (BigEggCrack2, Water, Kraken),
+            (SmallDragon, Water, Nessi),
+            (Nessi, Restart, Egg),
+            (Kraken, Sun, Jellyfish),
+            (Jellyfish, Restart, Egg),
+        ])
+        .with_crack_chains(&[&[EggCrack1, EggCrack2], &[BigEggCrack1, BigEggCrack2]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_state_is_reachable() {
+        if let Err(orphans) = StateGraph::default().validate_reachability() {
+            panic!("unreachable states: {:?}", orphans);
+        }
+    }
+
+    #[test]
+    fn peek_follows_egg_cracks_to_the_hatchling() {
+        use ButtonType::*;
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert!(matches!(graph.peek(Egg, Sun), Some(Chick)));
+        assert!(matches!(graph.peek(Egg, Water), Some(BabyTurtle)));
+        assert!(matches!(graph.peek(BigEgg, Water), Some(Kraken)));
+        assert!(matches!(graph.peek(Chick, Arrowhead), Some(Bird)));
+        assert!(graph.peek(Chick, Sun).is_none());
+    }
+
+    #[test]
+    fn the_big_egg_can_also_be_laid_by_the_bird() {
+        use ButtonType::*;
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert_eq!(graph.peek(Bird, Sun), Some(BigEgg));
+        assert_eq!(graph.peek(BigEgg, Sun), Some(SmallDragon));
+        // of the two ways in, going back leads to the egg
+        assert_eq!(graph.parent(BigEgg), Some(Egg));
+        assert_eq!(graph.crack_stage(BigEggCrack2), Some((1, 2)));
+    }
+
+    #[test]
+    fn depth_counts_from_the_closest_egg() {
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert_eq!(graph.depth(Egg), Some(0));
+        assert_eq!(graph.depth(BigEgg), Some(0));
+        assert_eq!(graph.depth(Chick), Some(3));
+        assert_eq!(graph.depth(Duck), Some(5));
+        assert_eq!(graph.depth(Kraken), Some(3));
+    }
+
+    #[test]
+    fn leaves_are_the_creatures_that_restart() {
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert!(graph.is_leaf(Jellyfish));
+        assert!(graph.is_leaf(Dragonmander));
+        assert!(!graph.is_leaf(Chick));
+        assert!(!graph.is_leaf(EggCrack1));
+    }
+
+    #[test]
+    fn shortest_paths_take_the_fewest_edges() {
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert_eq!(
+            graph.shortest_path(Egg, Duck),
+            Some(vec![Egg, EggCrack1, EggCrack2, Chick, Duckling, Duck])
+        );
+        assert_eq!(
+            graph.shortest_path(Egg, Nessi),
+            Some(vec![
+                Egg,
+                BigEgg,
+                BigEggCrack1,
+                BigEggCrack2,
+                SmallDragon,
+                Nessi
+            ])
+        );
+        assert_eq!(graph.shortest_path(Chick, Chick), Some(vec![Chick]));
+        assert_eq!(
+            graph.shortest_path(Duck, Chick),
+            Some(vec![Duck, Egg, EggCrack1, EggCrack2, Chick])
+        );
+    }
+
+    #[test]
+    fn states_by_distance_start_with_the_closest() {
+        let graph = StateGraph::default();
+        let order = graph.by_distance(WorldState::Egg);
+        assert_eq!(order[0], WorldState::Egg);
+        let distance = |to| graph.shortest_path(WorldState::Egg, to).unwrap().len();
+        for pair in order.windows(2) {
+            assert!(distance(pair[0]) <= distance(pair[1]), "{:?}", pair);
+        }
+        for (i, state) in order.iter().enumerate() {
+            assert!(!order[..i].contains(state), "{:?} twice", state);
+        }
+    }
+
+    #[test]
+    fn ways_into_hatchlings_start_at_their_egg() {
+        use ButtonType::*;
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert_eq!(graph.ways_into(Chick), [(Egg, Sun)]);
+        assert_eq!(graph.ways_into(BigEgg), [(Egg, Arrowhead), (Bird, Sun)]);
+        assert_eq!(graph.ways_into(Egg).len(), 6);
+    }
+
+    #[test]
+    fn parents_are_unique_or_none() {
+        use WorldState::*;
+        let graph = StateGraph::default();
+        assert_eq!(graph.parent(Duckling), Some(Chick));
+        assert_eq!(graph.parent(Chick), Some(EggCrack2));
+        assert_eq!(graph.parent(EggCrack1), Some(Egg));
+        assert_eq!(graph.parent(Egg), None);
+    }
+
+    #[test]
+    fn crack_stages_count_along_their_chain() {
+        use ButtonType::*;
+        use WorldState::*;
+        let graph = StateGraph::from_edges(&[
+            (Egg, Sun, EggCrack1),
+            (EggCrack1, Sun, EggCrack2),
+            (EggCrack2, Sun, BigEggCrack1),
+            (BigEggCrack1, Sun, Chick),
+        ])
+        .with_crack_chains(&[&[EggCrack1, EggCrack2, BigEggCrack1]]);
+        assert_eq!(graph.crack_stage(EggCrack2), Some((1, 3)));
+        assert_eq!(graph.crack_stage(BigEggCrack1), Some((2, 3)));
+        assert!(!graph.is_crack(Egg));
+        assert_eq!(graph.peek(Egg, Sun), Some(Chick));
+    }
+
+    #[test]
+    fn dot_has_every_edge_labeled() {
+        let dot = StateGraph::default().to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert!(dot.contains("Duckling [label=\"Duckling\"];"));
+        assert!(dot.contains("TurtleWizard [label=\"Turtle Wizard\"];"));
+        assert!(dot.contains("EggCrack1 [label=\"Cracking Egg\", style=dashed];"));
+        assert!(dot.contains("Chick -> Duckling [label=\"Water\"];"));
+        assert_eq!(dot.matches(" -> ").count(), 30);
+    }
+
+    #[test]
+    fn the_graph_file_is_the_default_graph() {
+        let text = std::fs::read_to_string("assets/states.txt").unwrap();
+        let graph = StateGraph::parse(&text).unwrap();
+        assert_eq!(graph.to_dot(), StateGraph::default().to_dot());
+        assert_eq!(graph.crack_chains, StateGraph::default().crack_chains);
+    }
+
+    #[test]
+    fn graph_files_report_the_line_thats_wrong() {
+        let error = |text| StateGraph::parse(text).err().unwrap().to_string();
+        assert_eq!(
+            error("egg sun chick\negg moon chick"),
+            "line 2: unknown button moon"
+        );
+        assert_eq!(
+            error("\n# nothing\negg sun chicken"),
+            "line 3: unknown state chicken"
+        );
+        assert_eq!(error("egg sun"), "line 1: expected <from> <button> <to>");
+        assert_eq!(error("cracks eggcrack1 egg9"), "line 1: unknown state egg9");
+    }
+
+    #[test]
+    fn orphans_are_reported() {
+        use ButtonType::*;
+        use WorldState::*;
+        let graph = StateGraph::from_edges(&[(Egg, Sun, Chick), (BigEgg, Water, Kraken)]);
+        let orphans = graph.validate_reachability().unwrap_err();
+        assert!(orphans.iter().any(|s| matches!(s, Heron)));
+        assert!(!orphans
+            .iter()
+            .any(|s| matches!(s, Chick | Kraken | Egg | BigEgg)));
+    }
+
+    #[test]
+    fn the_default_graph_is_valid() {
+        assert!(StateGraph::default().validate().is_ok());
+    }
+
+    #[test]
+    fn a_crack_that_cant_go_on_with_its_button_is_rejected() {
+        let mut graph = StateGraph::default();
+        graph.edges[WorldState::EggCrack1 as usize][ButtonType::Water as usize] = None;
+        assert!(matches!(graph.validate(), Err(GameError::Graph(_))));
+    }
+
+    #[test]
+    fn a_dead_end_is_rejected() {
+        let mut graph = StateGraph::default();
+        graph.edges[WorldState::Heron as usize][ButtonType::Restart as usize] = None;
+        assert!(matches!(graph.validate(), Err(GameError::Graph(_))));
+    }
+}
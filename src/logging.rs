@@ -0,0 +1,103 @@
+//! Leveled logging for diagnosing unattended deployments, to stderr on native and the browser console on the web.
+//!
+//! The level comes from the `RUST_LOG` environment variable (`error`, `warn`, `info` or `debug`), `info` by default.
+//! The output goes through macroquad's logging, which already knows how to reach the console on every platform.
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+};
+
+/// what's been logged at `info` or above and not yet taken into the in-game event log,
+/// just as bounded, in case it's never taken, e.g. in tests
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn from_name(name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" | "trace" => Some(Level::Debug),
+            _ => None,
+        }
+    }
+}
+
+/// the most detailed level that's logged, read once
+fn max_level() -> Level {
+    static MAX_LEVEL: OnceLock<Level> = OnceLock::new();
+    *MAX_LEVEL.get_or_init(|| {
+        std::env::var("RUST_LOG")
+            .ok()
+            .and_then(|name| Level::from_name(&name))
+            .unwrap_or(Level::Info)
+    })
+}
+
+pub fn log(level: Level, message: &str) {
+    if level <= Level::Info {
+        remember(level, message);
+    }
+    if level > max_level() {
+        return;
+    }
+    use macroquad::logging;
+    match level {
+        Level::Error => logging::error!("[error] {}", message),
+        Level::Warn => logging::warn!("[warn] {}", message),
+        Level::Info => logging::info!("[info] {}", message),
+        Level::Debug => logging::debug!("[debug] {}", message),
+    }
+}
+
+fn remember(level: Level, message: &str) {
+    let Ok(mut recent) = RECENT.lock() else {
+        return;
+    };
+    if recent.len() == crate::event_log::CAPACITY {
+        recent.pop_front();
+    }
+    let level = format!("{:?}", level).to_ascii_lowercase();
+    recent.push_back(format!("[{}] {}", level, message));
+}
+
+/// everything remembered for the event log since the last call, the oldest first
+pub fn take_recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|mut recent| recent.drain(..).collect())
+        .unwrap_or_default()
+}
+
+macro_rules! log_error {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Error, &format!($($arg)+))
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Warn, &format!($($arg)+))
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Info, &format!($($arg)+))
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Debug, &format!($($arg)+))
+    };
+}
@@ -1,18 +1,122 @@
-use std::f32::consts::PI;
+// first, so that its macros are available in all the other modules
+#[macro_use]
+mod logging;
+mod bindings;
+mod chart;
+mod cheats;
+mod combo;
+mod config;
+mod cursor;
+mod daily;
+mod dump;
+mod egg_selection;
+mod error;
+mod event_log;
+mod export;
+mod gallery;
+#[cfg(test)]
+mod golden;
+mod graph;
+mod music;
+mod overlay;
+mod particles;
+mod persistence;
+mod profile;
+mod rng;
+mod save;
+mod script;
+mod session;
+mod settings;
+mod simulation;
+mod speedrun;
+mod stats;
+mod strings;
+mod texture_cache;
+mod transition;
+mod view;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bindings::{Action, KeyBindings};
 use collections::storage;
+use combo::Combo;
+use config::{Config, Flash, TapThresholds};
 use coroutines::start_coroutine;
+use cursor::Cursor;
+use daily::CreatureOfTheDay;
+use error::GameError;
+use event_log::EventLog;
+use graph::StateGraph;
 use macroquad::{
-    audio::{self, play_sound_once, PlaySoundParams, Sound},
+    audio::{self, PlaySoundParams, Sound},
     prelude::*,
 };
+use music::Music;
+use overlay::Overlay;
+use particles::Particles;
+use profile::Profile;
+use save::Discovered;
+use session::SessionLog;
+use settings::Preferences;
+use simulation::{Event, Simulation};
+use speedrun::Speedrun;
+use stats::Stats;
+use strings::Strings;
+use texture_cache::TextureCache;
+use transition::{Transition, TransitionType, Warp};
 
 const WORLD_WIDTH: f32 = 2480.;
 const WORLD_HEIGHT: f32 = 3508.;
 const WORLD_STATE_VARIANTS: usize = 20;
-const ASSET_PATH: &'static str = "assets/";
+const ASSET_PATH: &str = "assets/";
+/// how far off a state texture's aspect ratio may be from the world's, relatively, before it's reported
+const ASPECT_RATIO_TOLERANCE: f32 = 0.01;
+/// all states in one image, with `--spritesheet`
+const SPRITESHEET_FILE: &str = "states.png";
+/// transition speed multiplier while the slow motion key (Shift) is held
+const SLOW_MOTION_SPEED: f32 = 0.25;
+/// how far drop shadows are shifted to the bottom right, in world units
+const SHADOW_OFFSET: f32 = 40.;
+/// opacity of a drop shadow behind something fully opaque
+const SHADOW_ALPHA: f32 = 0.45;
+/// how long a button has to be hovered before its tooltip shows up, in seconds
+const TOOLTIP_DELAY: f32 = 0.5;
+/// a frame taking longer than this, in seconds, means the window was away, see [`frame_delta`]
+const MAX_FRAME_DELTA: f32 = 0.5;
+/// how long a one-shot sound takes to fade out when its transition is cut short, in seconds
+const SOUND_FADE_OUT: f32 = 0.15;
+/// how long buttons take to fade in or out, in seconds
+const BUTTON_FADE: f32 = 0.3;
+/// how long a button's tint takes from one state's color to the next, in seconds
+const BUTTON_TINT_FADE: f32 = 0.08;
+/// how visible disabled buttons are at most with `--disabled-buttons=grey`
+const GREYED_OUT_ALPHA: f32 = 0.3;
+/// buttons can only be clicked once they're at least this visible
+const CLICKABLE_VISIBILITY: f32 = 0.8;
+/// what a shiny creature's texture is multiplied with, a golden shift of its colors
+const SHINY_TINT: Color = Color::new(1., 0.82, 0.45, 1.);
+/// how long the creature has to be pressed to show its description, in seconds, also the default hold for `--long-press`
+const LONG_PRESS: f32 = 0.6;
+/// the text in leaf states, next to the restart button
+const END_NOTE_KEY: &str = "ui.end-note";
+/// how long the note in leaf states takes to fade in, in seconds
+const END_NOTE_FADE_IN: f32 = 1.5;
+/// how fast the hint arrow pulses, in radians per second
+const HINT_PULSE_SPEED: f32 = 4.;
+/// how long the hint arrow takes to fade in, in seconds
+const HINT_FADE_IN: f32 = 1.;
+/// the prompt over the egg on unattended displays, see [`World::draw_attract_message`]
+const ATTRACT_KEY: &str = "ui.attract";
+/// how long the prompt takes to fade in, in seconds
+const ATTRACT_FADE_IN: f32 = 3.;
+/// how fast the prompt pulses, in radians per second, slower than the hint so that it stays calm
+const ATTRACT_PULSE_SPEED: f32 = 1.5;
+/// how long the button that led to a creature is shown in the corner after arriving, in seconds
+const ORIGIN_SHOW: f32 = 2.5;
+/// of which the last bit is spent fading out
+const ORIGIN_FADE: f32 = 0.5;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum WorldState {
     Egg,
     EggCrack1,
@@ -36,648 +140,2458 @@ enum WorldState {
     Jellyfish,
 }
 
-#[derive(Clone, Copy)]
+impl WorldState {
+    /// every state in enum order, which is also the order of the texture files
+    const ALL: [WorldState; WORLD_STATE_VARIANTS] = {
+        use WorldState::*;
+        [
+            Egg,
+            EggCrack1,
+            EggCrack2,
+            Chick,
+            Duckling,
+            Duck,
+            Bird,
+            Heron,
+            BabyTurtle,
+            Salamander,
+            Dragonmander,
+            Turtle,
+            TurtleWizard,
+            BigEgg,
+            BigEggCrack1,
+            BigEggCrack2,
+            SmallDragon,
+            Nessi,
+            Kraken,
+            Jellyfish,
+        ]
+    };
+
+    pub fn all() -> impl Iterator<Item = WorldState> {
+        Self::ALL.into_iter()
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        use WorldState::*;
+        match self {
+            Egg => "Egg",
+            EggCrack1 => "Cracking Egg",
+            EggCrack2 => "Hatching Egg",
+            Chick => "Chick",
+            Duckling => "Duckling",
+            Duck => "Duck",
+            Bird => "Bird",
+            Heron => "Heron",
+            BabyTurtle => "Baby Turtle",
+            Salamander => "Salamander",
+            Dragonmander => "Dragonmander",
+            Turtle => "Turtle",
+            TurtleWizard => "Turtle Wizard",
+            BigEgg => "Big Egg",
+            BigEggCrack1 => "Cracking Big Egg",
+            BigEggCrack2 => "Hatching Big Egg",
+            SmallDragon => "Small Dragon",
+            Nessi => "Nessi",
+            Kraken => "Kraken",
+            Jellyfish => "Jellyfish",
+        }
+    }
+
+    /// the key of this creature's description in the texts, eggs and cracks have none
+    pub fn description_key(&self) -> Option<String> {
+        use WorldState::*;
+        match self {
+            Egg | EggCrack1 | EggCrack2 | BigEgg | BigEggCrack1 | BigEggCrack2 => None,
+            _ => Some(format!(
+                "creature.{}.desc",
+                format!("{:?}", self).to_ascii_lowercase()
+            )),
+        }
+    }
+
+    /// looks a state up by its variant name, ignoring case
+    pub fn from_name(name: &str) -> Option<WorldState> {
+        Self::ALL
+            .into_iter()
+            .find(|state| format!("{:?}", state).eq_ignore_ascii_case(name))
+    }
+
+    /// the state `offset` steps further in enum order, wrapping around at both ends
+    fn cycled(self, offset: isize) -> WorldState {
+        let len = WORLD_STATE_VARIANTS as isize;
+        Self::ALL[(self as isize + offset).rem_euclid(len) as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 enum SoundIndex {
     Crack1,
     Crack2,
     Scale1,
     Scale2,
+    /// the jingle for discovering a leaf creature
+    Celebrate,
+    /// the mouse moving onto a button, only played with `--hover-sound`
+    Hover,
+    /// a button being pressed down
+    Press,
+    /// the notes above the first two, only played with `--melody`
+    Scale3,
+    Scale4,
+    Scale5,
+}
+
+impl SoundIndex {
+    /// the scale notes from the lowest up: D6, E6, F#6, G6 and A6
+    pub const SCALE: [SoundIndex; 5] = [
+        SoundIndex::Scale1,
+        SoundIndex::Scale2,
+        SoundIndex::Scale3,
+        SoundIndex::Scale4,
+        SoundIndex::Scale5,
+    ];
 }
 
 struct World {
     buttons: [Button; 4],
+    /// one per state in index order, or a single spritesheet holding them all
     state_textures: Vec<Texture2D>,
-    sounds: [Sound; 4],
-    // state machine
-    state: WorldState,
-    transition: Option<Transition>,
+    /// columns and rows of the spritesheet, if the states come from one
+    spritesheet_grid: Option<(u32, u32)>,
+    /// with `--texture-cache`, in place of `state_textures`
+    texture_cache: Option<TextureCache>,
+    /// the textures still to be decoded ahead of time, one per rendered frame while idle, the next one last,
+    /// see [`World::warm_textures`]
+    warming: Vec<WorldState>,
+    /// in `SoundIndex` order, None for the optional sounds that couldn't be loaded
+    sounds: Vec<Option<Sound>>,
+    /// played as each state is reached, indexed by `WorldState`, see `--reveal-sound`
+    reveal_sounds: Vec<Option<Sound>>,
+    /// the one-shot sound played last, which may still be sounding
+    playing: Option<OneShot>,
+    music: Music,
+    sim: Simulation,
+    /// where the progress is saved to, None while reviewing or exporting
+    profile: Option<Profile>,
+    discovered: Discovered,
+    stats: Stats,
+    /// only with `--log-sessions`, see [`session`]
+    session: Option<SessionLog>,
+    combo: Combo,
+    /// with `--speedrun`
+    speedrun: Option<Speedrun>,
+    /// with `--creature-of-the-day`
+    daily: Option<CreatureOfTheDay>,
+    particles: Particles,
+    bindings: KeyBindings,
+    preferences: Preferences,
+    strings: Strings,
+    /// None for macroquad's default font, see [`strings::load_font`]
+    font: Option<Font>,
+    /// seconds since the world last came to rest, for showing which button led here
+    origin_time: f32,
+    flash: CrackFlash,
+    /// seconds without any input while [`World::is_idle`], for hinting at a button once it's long enough
+    idle_time: f32,
+    /// for how long the creature (not a button) has been pressed at rest, in seconds
+    press_time: f32,
+    /// None while the system cursor is shown
+    cursor: Option<Cursor>,
+    /// the logo, None without one
+    overlay: Option<Overlay>,
+    /// index into `buttons` of the button with keyboard focus, independent of the mouse
+    focused: Option<usize>,
+    /// where the mouse was in the world during the last input handling
+    mouse_pos: Vec2,
+    /// the window size the buttons were last fitted to, in logical pixels, and the camera's pixels per
+    /// world unit then, which changes without a resize when toggling the crisp camera
+    fitted_screen: (Vec2, f32),
+    /// whether the slow motion key is held right now, which slows transitions down on top of the configured speed
+    slow_motion: bool,
+    /// clicking for the player with `--script`, until it's done
+    script: Option<script::Player>,
+    /// only ever shown with `--cheats`, see [`cheats`]
+    debug_overlay: bool,
+    /// the outlines of all buttons, shown or not, also only with `--cheats`
+    show_hitboxes: bool,
+    /// which first button the leaf creatures were reached by, also only with `--cheats`
+    show_button_stats: bool,
+    /// nothing moves on while paused, except by a step at a time, also only with `--cheats`
+    paused: bool,
+    /// a step was asked for while paused, to be taken in the next progress
+    step_pending: bool,
+    /// how many seconds of transition time the frame is rendered ahead of the last fixed step
+    render_ahead: f32,
+    /// why the state graph couldn't be reloaded the last time, shown until it can
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    graph_error: Option<GameError>,
+    event_log: EventLog,
+    config: Config,
+}
+
+/// Logs the failure to load an asset the game can't do without before giving up.
+fn required<T>(loaded: Result<T, macroquad::Error>, what: &str) -> T {
+    loaded.unwrap_or_else(|e| {
+        GameError::Asset {
+            path: what.to_string(),
+            message: e.to_string(),
+        }
+        .exit()
+    })
 }
 
 use smallvec::SmallVec;
 impl World {
-    async fn load_textures() -> Vec<Texture2D> {
+    /// The spritesheet with the grid it's divided into, if one is configured, falling back to
+    /// the textures of every state otherwise, or if the spritesheet is missing.
+    async fn load_state_textures(config: &Config) -> (Vec<Texture2D>, Option<(u32, u32)>) {
+        if let Some(grid) = config.spritesheet {
+            let path = format!("{}{}", ASSET_PATH, SPRITESHEET_FILE);
+            match load_texture(&path).await {
+                Ok(texture) => return (vec![texture], Some(grid)),
+                Err(e) => log_warn!(
+                    "loading every state on its own, couldn't load {}: {}",
+                    path,
+                    e
+                ),
+            }
+        }
+        (Self::load_textures(config.sequential_loading).await, None)
+    }
+
+    /// All state textures, in parallel unless `sequential` asks for them one by one in index order.
+    async fn load_textures(sequential: bool) -> Vec<Texture2D> {
         let file_paths: SmallVec<[String; WORLD_STATE_VARIANTS]> = (0..WORLD_STATE_VARIANTS)
-            .map(|i| (ASSET_PATH.to_string() + i.to_string().as_str() + ".png"))
+            .map(|i| ASSET_PATH.to_string() + i.to_string().as_str() + ".png")
             .collect();
+        if sequential {
+            let mut textures = Vec::with_capacity(WORLD_STATE_VARIANTS);
+            for path in file_paths.iter() {
+                textures.push(required(load_texture(path).await, path));
+            }
+            return textures;
+        }
         let loaded_textures =
             futures::future::try_join_all(file_paths.iter().map(|path| load_texture(path))).await;
-        loaded_textures.unwrap()
+        let textures = required(loaded_textures, "the state textures");
+        log_info!("loaded {} state textures", textures.len());
+        textures
     }
 
-    async fn load_sounds() -> [Sound; 4] {
-        [
-            audio::load_sound((ASSET_PATH.to_string() + "crack1.mp3").as_str())
-                .await
-                .unwrap(),
-            audio::load_sound((ASSET_PATH.to_string() + "crack2.mp3").as_str())
-                .await
-                .unwrap(),
-            audio::load_sound((ASSET_PATH.to_string() + "scale-d6.mp3").as_str())
-                .await
-                .unwrap(),
-            audio::load_sound((ASSET_PATH.to_string() + "scale-e6.mp3").as_str())
-                .await
-                .unwrap(),
-        ]
+    /// a sound, or None if it couldn't be loaded, which is logged once here and then kept quiet about
+    async fn load_optional_sound(file: &str, what: &str) -> Option<Sound> {
+        match audio::load_sound((ASSET_PATH.to_string() + file).as_str()).await {
+            Ok(sound) => Some(sound),
+            Err(e) => {
+                let error = GameError::Audio {
+                    file: file.to_string(),
+                    message: e.to_string(),
+                };
+                log_warn!("no {}, {}", what, error);
+                None
+            }
+        }
     }
 
-    pub async fn new() -> Self {
-        Self {
-            buttons: Button::create().await,
-            state_textures: Self::load_textures().await,
-            sounds: Self::load_sounds().await,
-            state: WorldState::Egg,
-            transition: None,
+    /// The sounds in `SoundIndex` order, each None if it couldn't be loaded, so that one broken file
+    /// only silences itself.
+    async fn load_sounds() -> Vec<Option<Sound>> {
+        let files = [
+            ("crack1.mp3", "crack sound"),
+            ("crack2.mp3", "hatching crack sound"),
+            ("scale-d6.mp3", "scale note"),
+            ("scale-e6.mp3", "scale note"),
+            ("celebrate.mp3", "celebration jingle"),
+            ("hover.mp3", "hover sound"),
+            ("press.mp3", "press sound"),
+            ("scale-fs6.mp3", "melody note"),
+            ("scale-g6.mp3", "melody note"),
+            ("scale-a6.mp3", "melody note"),
+        ];
+        let mut sounds = Vec::with_capacity(files.len());
+        for (file, what) in files {
+            sounds.push(Self::load_optional_sound(file, what).await);
         }
+        log_info!("loaded {} sounds", sounds.iter().flatten().count());
+        sounds
     }
 
-    pub fn handle_input(&mut self, cam: &Camera2D) {
-        let mut clicked_button = None;
-        for button in self.buttons.iter_mut() {
-            if button.disabled {
-                continue;
-            }
-            let clicked = button.update_button_state(cam);
-            // TODO: handle clicked (by triggering a WorldState transistion and removing the button)
-            if clicked {
-                clicked_button = Some(button.b_type);
-                button.disable();
+    /// `font` is loaded before anything else, since the loading screen already needs it.
+    pub async fn new(config: Config, font: Option<Font>) -> Self {
+        let mut sim = Simulation::new(
+            config.transitions.clone(),
+            macroquad::miniquad::date::now() as u64,
+        );
+        sim.interruptible = config.interruptible;
+        sim.unwind = config.unwind;
+        sim.shiny_chance = config.shiny_chance;
+        let sounds = Self::load_sounds().await;
+        let mut reveal_sounds = Vec::with_capacity(WORLD_STATE_VARIANTS);
+        for file in config.reveal_sounds.iter() {
+            reveal_sounds.push(match file {
+                Some(file) => Self::load_optional_sound(file, "reveal sound").await,
+                None => None,
+            });
+        }
+        if config.melody {
+            // as far up the scale as the notes go without a gap
+            sim.melody_notes = SoundIndex::SCALE
+                .iter()
+                .take_while(|&&note| sounds[note as usize].is_some())
+                .count();
+        }
+        sim.navigation = config.navigation;
+        sim.sync_enabled_to_state();
+        if let Err(e) = sim.graph.validate() {
+            log_warn!("{}", e);
+        }
+        let daily = config
+            .creature_of_the_day
+            .then(|| CreatureOfTheDay::today(&sim.graph))
+            .flatten();
+        let mut music = Music::load(config.effective_volume()).await;
+        music.play(WorldState::Egg.music_track());
+        let mut buttons = Button::create(config.button_layout).await;
+        for button in buttons.iter_mut() {
+            button.long_press = config.long_press;
+            button.taps = config.taps;
+        }
+        let filter = if config.crisp {
+            FilterMode::Nearest
+        } else {
+            FilterMode::Linear
+        };
+        let texture_cache = match config.texture_cache {
+            Some(capacity) => Some(TextureCache::load(capacity, filter).await),
+            None => None,
+        };
+        let (state_textures, spritesheet_grid) = if texture_cache.is_some() {
+            (Vec::new(), None)
+        } else {
+            Self::load_state_textures(&config).await
+        };
+        if config.crisp {
+            let textures = state_textures
+                .iter()
+                .chain(buttons.iter().map(|b| &b.texture));
+            for texture in textures {
+                texture.set_filter(FilterMode::Nearest);
             }
         }
-        if let Some(b_type) = clicked_button {
-            self.start_transition(b_type);
+        Self {
+            buttons,
+            state_textures,
+            spritesheet_grid,
+            texture_cache,
+            warming: Vec::new(),
+            sounds,
+            reveal_sounds,
+            playing: None,
+            music,
+            sim,
+            profile: None,
+            discovered: Discovered::default(),
+            stats: Stats::default(),
+            session: config.log_sessions.then(SessionLog::start),
+            combo: Combo::default(),
+            speedrun: config.speedrun.map(Speedrun::new),
+            daily,
+            particles: Particles::default(),
+            bindings: KeyBindings::load(),
+            preferences: Preferences::load(),
+            strings: Strings::load().await,
+            font,
+            press_time: 0.,
+            origin_time: ORIGIN_SHOW,
+            flash: CrackFlash::new(config.crack_flash),
+            idle_time: 0.,
+            cursor: Cursor::load(config.native_cursor).await,
+            overlay: Overlay::load(&config.logo).await,
+            focused: None,
+            mouse_pos: Vec2::ZERO,
+            fitted_screen: (Vec2::ZERO, 0.),
+            slow_motion: false,
+            debug_overlay: false,
+            show_hitboxes: false,
+            script: None,
+            show_button_stats: false,
+            paused: false,
+            step_pending: false,
+            render_ahead: 0.,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            graph_error: None,
+            event_log: EventLog::default(),
+            config,
         }
     }
 
-    pub fn progress(&mut self, delta_secs: f32) {
-        // progress the transition, if there is one
-        if let Some(mut t) = self.transition.take() {
-            let next_transition = t.progress(delta_secs);
-            if let Some(sound_index) = t.sound_to_play() {
-                self.play_sound(sound_index);
-            }
-            if t.completed() {
-                self.finish_transition(&t, next_transition);
-            } else {
-                // its slightly weird to but back the transition, but who knows, maybe the compiler is smart enough to make this free, maybe not
-                self.transition = Some(t);
+    pub fn handle_input(&mut self, cam: &Camera2D, delta_secs: f32) {
+        let mouse_pos = mouse_world_pos(cam);
+        let any_input = mouse_pos != self.mouse_pos
+            || !get_keys_down().is_empty()
+            || is_mouse_button_down(MouseButton::Left)
+            || !touches().is_empty();
+        if any_input {
+            self.idle_time = 0.;
+        }
+        self.mouse_pos = mouse_pos;
+        self.fit_buttons_to_screen(cam);
+        if let Some(TransitionType::Intro) = self.sim.transition.as_ref().map(|t| t.t_type) {
+            return;
+        }
+        self.slow_motion = self.bindings.down(Action::SlowMotion);
+        if self.bindings.pressed(Action::SkipTransition) && self.sim.transition.is_some() {
+            self.skip_transition();
+        }
+        if self.config.review {
+            self.handle_review_input();
+            return;
+        }
+        if self.bindings.pressed(Action::Back) {
+            self.sim.back();
+        }
+        // every button still updates its state, even if another one was clicked in the same frame
+        let pointer = Pointer::current(cam);
+        let mut clicks: SmallVec<[ButtonType; 4]> = SmallVec::new();
+        // at most one button sound per frame, a press wins over a hover
+        let mut feedback = None;
+        for button in self.buttons.iter_mut() {
+            let clickable =
+                self.sim.is_enabled(button.b_type) && button.visibility >= CLICKABLE_VISIBILITY;
+            match button.update_button_state(&pointer, delta_secs, clickable) {
+                Some(ButtonEvent::Clicked) => clicks.push(button.b_type),
+                Some(ButtonEvent::Entered) if self.config.hover_sound => {
+                    feedback.get_or_insert(SoundIndex::Hover);
+                }
+                Some(ButtonEvent::Pressed) => feedback = Some(SoundIndex::Press),
+                _ => {}
             }
         }
-    }
+        if let Some(sound_index) = feedback {
+            self.play_sound(sound_index);
+        }
+        clicks.extend(self.handle_focus_input());
+        if !clicks.is_empty() {
+            log_debug!("clicked {:?} in {:?}", clicks, self.sim.state);
+            self.record_event(format!("clicked {:?} in {:?}", clicks, self.sim.state));
+        }
+        if self.sim.click_first(&clicks) {
+            self.on_trigger();
+        }
 
-    fn play_sound(&self, sound_index: SoundIndex) {
-        use SoundIndex::*;
-        let volume = match sound_index {
-            Scale1 | Scale2 => 0.7,
-            Crack1 | Crack2 => 1.1,
+        let pressing_creature = is_mouse_button_down(MouseButton::Left)
+            && self.sim.transition.is_none()
+            && !self.hovering_button();
+        self.press_time = if pressing_creature {
+            self.press_time + delta_secs
+        } else {
+            0.
         };
-        macroquad::audio::play_sound(
-            &self.sounds[sound_index as usize],
-            PlaySoundParams {
-                looped: false,
-                volume,
-            },
-        );
     }
 
-    /// Some transitions require a final action, such as the restart or enabling the restart button
-    fn finish_transition(&mut self, t: &Transition, next_transition: Option<Transition>) {
-        use WorldState::*;
-        match t.goal_state {
-            Egg => self.init_buttons(),
-            Duck | Heron | Dragonmander | TurtleWizard | Nessi | Jellyfish => {
-                self.buttons[3].disabled = false;
-            }
-            _ => {}
-        };
-        self.state = t.goal_state;
-
-        // this whole process of continuing from one transition into the next is dirty, but for what I'm doing now it works
-        if let Some(ref new_t) = next_transition {
-            if let Some(sound_index) = new_t.sound_to_play() {
-                play_sound_once(&self.sounds[sound_index as usize]);
-            }
+    /// The player (or the script) clicked a button that started a transition.
+    fn on_trigger(&mut self) {
+        self.combo.on_trigger();
+        let restarting = self
+            .sim
+            .transition
+            .as_ref()
+            .and_then(|t| t.t_type.trigger())
+            == Some(ButtonType::Restart);
+        if restarting {
+            self.stop_all_loops();
         }
-        self.transition = next_transition;
-    }
-
-    fn texture_for_state(&self, state: WorldState) -> &Texture2D {
-        &self.state_textures[state as usize]
-    }
-
-    /// draws the main image and after that the buttons
-    pub fn render(&self) {
-        let params = DrawTextureParams {
-            dest_size: Some(Vec2::new(WORLD_WIDTH, WORLD_HEIGHT)),
-            ..Default::default()
-        };
-        // in case of a transition draw both images with their respecting alpha according to the transition
-        if let Some(ref t) = self.transition {
-            let (color_current, color_next) = t.colors();
-            draw_texture_ex(
-                self.texture_for_state(self.state),
-                0.,
-                0.,
-                color_current,
-                params.clone(),
-            );
-            draw_texture_ex(
-                self.texture_for_state(t.goal_state),
-                0.,
-                0.,
-                color_next,
-                params,
-            );
-        } else {
-            draw_texture_ex(self.texture_for_state(self.state), 0., 0., WHITE, params);
-            for button in self.buttons.iter() {
-                button.draw();
-            }
+        if let Some(ref mut speedrun) = self.speedrun {
+            // the state only changes once the transition is done
+            speedrun.on_trigger(self.sim.state);
         }
     }
 
-    fn start_transition(&mut self, b_type: ButtonType) {
-        use WorldState::*;
-        // compute the target
-        let goal_state = match self.state {
-            Egg => match b_type {
-                ButtonType::Sun | ButtonType::Water => EggCrack1,
-                ButtonType::Arrowhead => BigEgg,
-                _ => Egg,
-            },
-            EggCrack1 => panic!("started transition in egg crack!"),
-            EggCrack2 => panic!("started transition in egg crack!"),
-            Chick => match b_type {
-                ButtonType::Sun => panic!("sun no longer available!"),
-                ButtonType::Water => Duckling,
-                ButtonType::Arrowhead => Bird,
-                _ => Egg,
-            },
-            Duckling => match b_type {
-                ButtonType::Sun => panic!("sun no longer available!"),
-                ButtonType::Water => panic!("water no longer available!"),
-                ButtonType::Arrowhead => Duck,
-                _ => Egg,
-            },
-            Duck => Egg,
-            Bird => match b_type {
-                ButtonType::Sun => panic!("sun no longer available!"),
-                ButtonType::Water => Heron,
-                ButtonType::Arrowhead => panic!("arrow no longer available!"),
-                _ => Egg,
-            },
-            Heron => Egg,
-            BabyTurtle => match b_type {
-                ButtonType::Sun => Salamander,
-                ButtonType::Water => panic!("water no longer available!"),
-                ButtonType::Arrowhead => Turtle,
-                _ => Egg,
-            },
-            Salamander => match b_type {
-                ButtonType::Sun => panic!("sun no longer available!"),
-                ButtonType::Water => panic!("water no longer available!"),
-                ButtonType::Arrowhead => Dragonmander,
-                _ => Egg,
-            },
-            Dragonmander => Egg,
-            Turtle => match b_type {
-                ButtonType::Sun => TurtleWizard,
-                ButtonType::Water => panic!("water no longer available!"),
-                ButtonType::Arrowhead => panic!("arrow no longer available!"),
-                _ => Egg,
-            },
-            TurtleWizard => Egg,
-            BigEgg => BigEggCrack1,
-            BigEggCrack1 => panic!("started transition in egg crack!"),
-            BigEggCrack2 => panic!("started transition in egg crack!"),
-            SmallDragon => match b_type {
-                ButtonType::Sun => panic!("sun no longer available!"),
-                ButtonType::Water => Nessi,
-                ButtonType::Arrowhead => panic!("arrow no longer available!"),
-                _ => Egg,
-            },
-            Nessi => Egg,
-            Kraken => match b_type {
-                ButtonType::Sun => Jellyfish,
-                ButtonType::Water => panic!("water no longer available!"),
-                ButtonType::Arrowhead => panic!("arrow no longer available!"),
-                _ => Egg,
-            },
-            Jellyfish => Egg,
-        };
-        // start the new transition
-        let t_type = match goal_state {
-            EggCrack1 | BigEggCrack1 => TransitionType::EggCracking(b_type),
-            _ => TransitionType::Regular,
-        };
-        let new_transition = Transition::new(goal_state, t_type);
-        self.transition = Some(new_transition);
+    /// Whether nothing is moving: no transition running or queued up, no burst of particles flying, no crack
+    /// flash and no button fading in or out. A state's ambience never stops, so it doesn't count, and neither
+    /// does the camera easing into a resized window, which is the main loop's.
+    pub fn is_idle(&self) -> bool {
+        self.sim.transition.is_none()
+            && self.sim.queued().next().is_none()
+            && !self.particles.is_bursting()
+            && self.flash.is_over()
+            && !self.buttons.iter().any(Button::is_fading)
     }
 
-    fn init_buttons(&mut self) {
-        let buttons = &mut self.buttons;
-        buttons[0].disabled = false;
-        buttons[1].disabled = false;
-        buttons[2].disabled = false;
-        buttons[3].disabled = true;
+    /// What each clickable button leads to from here, see [`Simulation::current_options`].
+    pub fn current_options(&self) -> simulation::Options {
+        self.sim.current_options()
     }
-}
-
-/// used to differentiate the two kinds of transitions existing, but also the two sounds in the game
-#[derive(Clone, Copy)]
-enum TransitionType {
-    Regular,
-    EggCracking(ButtonType),
-}
 
-struct Transition {
-    goal_state: WorldState,
-    t_type: TransitionType,
-    time_progressed: f32,
-    sound_trigger: bool,
-}
+    /// keeps the buttons big enough to tap, recomputed whenever the window is resized or the camera zooms
+    fn fit_buttons_to_screen(&mut self, cam: &Camera2D) {
+        let screen = vec2(screen_width(), screen_height());
+        let pixels_per_unit = view::pixels_per_unit(cam);
+        if (screen, pixels_per_unit) == self.fitted_screen {
+            return;
+        }
+        self.fitted_screen = (screen, pixels_per_unit);
+        for button in self.buttons.iter_mut() {
+            button.fit_to_tap_size(self.config.min_tap_size, pixels_per_unit);
+        }
+    }
 
-impl Transition {
-    pub fn new(goal_state: WorldState, t_type: TransitionType) -> Self {
-        Self {
-            goal_state,
-            t_type,
-            time_progressed: 0.,
-            sound_trigger: false,
-        }
-    }
-
-    /// Progresses the transition and returns None, except if there is a subsequent transition that it continues into.
-    /// In that case it starts that transition with the leftover time and returns it.
-    pub fn progress(&mut self, delta_time: f32) -> Option<Transition> {
-        let time_old = self.time_progressed;
-        self.time_progressed += delta_time;
-        // check for sound to play
-        self.update_sound_to_play(time_old, self.time_progressed);
-
-        if self.completed() {
-            let total = self.total_duration();
-            let leftover_delta = self.time_progressed - total;
-            self.time_progressed = total;
-            // the following builds on the assumption that the leftover_delta is not enough to complete the subsequent transition too,
-            // which it won't looking at how long transitions are taking in this toy program
-            let mut subsequent = self.subsequent_transition();
-            if let Some(ref mut t) = subsequent {
-                t.progress(leftover_delta);
-            }
-            return subsequent;
+    /// Tab/Shift-Tab (or whatever they're bound to) move the keyboard focus, Enter/Space activate
+    /// the focused button, which is then handled just like a click.
+    fn handle_focus_input(&mut self) -> Option<ButtonType> {
+        if self.bindings.pressed(Action::NextFocus) {
+            let backwards = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            self.move_focus(backwards);
+        }
+        if self.bindings.pressed(Action::Activate) {
+            return Some(self.buttons[self.focused?].b_type);
         }
         None
     }
 
-    fn total_duration(&self) -> f32 {
-        use TransitionType::*;
-        match self.t_type {
-            Regular => 9.3,
-            EggCracking(_) => 3.0,
+    /// moves the focus to the next (or previous) enabled button, wrapping around at the ends
+    fn move_focus(&mut self, backwards: bool) {
+        let len = self.buttons.len();
+        // without focus, start in front of the first (or behind the last) button
+        let start = self.focused.unwrap_or(if backwards { 0 } else { len - 1 });
+        self.focused = (1..=len)
+            .map(|step| {
+                if backwards {
+                    (start + len - step) % len
+                } else {
+                    (start + step) % len
+                }
+            })
+            .find(|&i| self.sim.is_enabled(self.buttons[i].b_type));
+    }
+
+    /// the effective multiplier on transition time, considering slow motion
+    fn current_transition_speed(&self) -> f32 {
+        if self.slow_motion {
+            self.config.transition_speed * SLOW_MOTION_SPEED
+        } else {
+            self.config.transition_speed
         }
     }
 
-    pub fn colors(&self) -> (Color, Color) {
-        let color_current_alpha;
-        let color_next_alpha;
-        match self.t_type {
-            TransitionType::Regular => {
-                let total = self.total_duration();
-                let relative_progress = self.time_progressed / total;
-                let alpha = if relative_progress <= 1. / 7. || relative_progress >= 6. / 7. {
-                    1.
-                } else {
-                    ((relative_progress * 2.8 * PI - 0.4 * PI).cos() + 1.) / 2.
-                };
-                (color_current_alpha, color_next_alpha) = if relative_progress <= 0.5 {
-                    (alpha, 0.)
-                } else {
-                    (0., alpha)
-                };
+    pub fn progress(&mut self, mut delta_secs: f32) {
+        if self.paused {
+            if !std::mem::take(&mut self.step_pending) {
+                return;
             }
-            TransitionType::EggCracking(_) => {
-                color_current_alpha = 1.;
-                color_next_alpha = 0.;
+            delta_secs = cheats::STEP_DELTA;
+        }
+        self.music.update(delta_secs);
+        self.combo.update(delta_secs);
+        if let Some(ref mut speedrun) = self.speedrun {
+            speedrun.update(delta_secs);
+        }
+        self.particles.update(delta_secs);
+        let ambience = self.config.ambience[self.sim.state as usize];
+        match ambience {
+            Some(ref emitter)
+                if self.sim.transition.is_none() && !self.preferences.reduced_motion =>
+            {
+                let dest = self.config.placements[self.sim.state as usize].dest();
+                self.particles.emit_ambient(emitter, dest, delta_secs);
             }
+            _ => self.particles.clear_ambient(),
         }
-
-        let color_current = Color {
-            r: 1.,
-            g: 1.,
-            b: 1.,
-            a: color_current_alpha,
-        };
-        let color_next = Color {
-            r: 1.,
-            g: 1.,
-            b: 1.,
-            a: color_next_alpha,
+        self.flash.update(delta_secs);
+        self.origin_time += delta_secs;
+        self.idle_time = if self.is_idle() {
+            self.idle_time + delta_secs
+        } else {
+            0.
         };
+        let shown = self.sim.buttons_shown() && !self.config.review;
+        for i in 0..self.buttons.len() {
+            let b_type = self.buttons[i].b_type;
+            let visible = self.sim.is_enabled(b_type) || self.greyed_out(b_type);
+            self.buttons[i].update_anim(
+                delta_secs,
+                shown && visible,
+                self.preferences.reduced_motion,
+            );
+        }
+        // only the incoming delta is scaled, so changing the speed mid-transition never makes it jump
+        self.update_sound_fade(delta_secs);
+        let delta_secs = delta_secs * self.current_transition_speed();
+        let from = self.sim.state;
+        let events = self.sim.progress(delta_secs);
+        self.handle_events(events, from);
+    }
 
-        (color_current, color_next)
+    /// `from` is the state the world was in before the events
+    fn handle_events(&mut self, events: simulation::Events, mut from: WorldState) {
+        for event in events {
+            match event {
+                Event::Sound(sound_index) => self.play_sound(sound_index),
+                Event::Reached(state) => {
+                    self.record_event(format!("reached {:?}", state));
+                    if let Some(ref mut session) = self.session {
+                        session.record_reached(from, state, &self.sim.graph);
+                    }
+                    let hatched = self.sim.graph.is_crack(from);
+                    if self.sim.graph.is_crack(state) && !self.preferences.reduced_motion {
+                        self.flash.start();
+                    }
+                    from = state;
+                    let new_leaf =
+                        self.sim.graph.is_leaf(state) && !self.discovered.contains(state);
+                    let daily = self.daily.as_mut().is_some_and(|d| d.on_reached(state));
+                    if self.shows_shiny(state) {
+                        if hatched && !self.preferences.reduced_motion {
+                            let dest = self.config.placements[state as usize].dest();
+                            self.particles.burst_sparkles(dest);
+                        }
+                        self.discovered.insert_shiny(state);
+                    }
+                    if new_leaf || daily {
+                        self.celebrate();
+                    }
+                    if daily && !self.preferences.reduced_motion {
+                        // the creature of the day gets a second round of confetti on top
+                        self.particles.burst_confetti();
+                    }
+                    self.play_reveal_sound(state);
+                    self.music.play(state.music_track());
+                    self.discovered.insert(state);
+                    self.stats.record_reached(state);
+                    if let Some(budget) = self.config.texture_budget {
+                        self.warm_textures(budget);
+                    }
+                    if let Some(button) = self
+                        .sim
+                        .first_button
+                        .filter(|_| self.sim.graph.is_leaf(state))
+                    {
+                        self.stats.record_leaf(state, button);
+                    }
+                    if let Some(ref mut speedrun) = self.speedrun {
+                        speedrun.on_reached(state);
+                    }
+                }
+                Event::Rest => {
+                    // only save once the world is at rest
+                    self.save();
+                    self.origin_time = 0.;
+                    // egg cracks continuing into each other don't count, only the player's own transitions
+                    self.combo.on_rest();
+                }
+            }
+        }
     }
 
-    fn update_sound_to_play(&mut self, time_old: f32, time_new: f32) {
-        let sound_start = match self.t_type {
-            TransitionType::Regular => self.total_duration() / 1.9,
-            TransitionType::EggCracking(_) => self.total_duration(),
+    /// Roughly how many bytes of GPU memory the textures take that are uploaded right now: every state's
+    /// (or the spritesheet), or only the cached ones with `--texture-cache`, and the buttons'.
+    /// Small extras like the cursor and the logo aren't counted.
+    pub fn texture_memory_bytes(&self) -> usize {
+        let states: usize = match self.texture_cache {
+            Some(ref cache) => cache.gpu_usage().1,
+            None => self.state_textures.iter().map(texture_bytes).sum(),
         };
-        // the check on self.sound_trigger is to make sure that the sound isn't triggered twice in edge cases
-        self.sound_trigger =
-            if time_old <= sound_start && sound_start <= time_new && !self.sound_trigger {
-                true
-            } else {
-                false
-            };
+        let buttons: usize = self.buttons.iter().map(|b| texture_bytes(&b.texture)).sum();
+        states + buttons
     }
 
-    pub fn sound_to_play(&self) -> Option<SoundIndex> {
-        if self.sound_trigger {
-            match self.t_type {
-                TransitionType::Regular => Some(if macroquad::rand::rand() % 2 == 0 {
-                    SoundIndex::Scale1
-                } else {
-                    SoundIndex::Scale2
-                }),
-                TransitionType::EggCracking(_) => match self.goal_state {
-                    WorldState::BigEggCrack1 | WorldState::EggCrack1 => Some(SoundIndex::Crack1),
-                    WorldState::BigEggCrack2 | WorldState::EggCrack2 => Some(SoundIndex::Crack2),
-                    _ => panic!("sound for crack requested but goal is no crack"),
-                },
-            }
-        } else {
-            None
+    /// Decodes as many of the state textures as fit into `budget_bytes` ahead of time with `--texture-cache`,
+    /// the ones fewest transitions away from the current state first, so that the next crossfades don't have
+    /// to wait for them. They're decoded one per frame while the world is idle, so that doing so doesn't
+    /// stutter the animation, any still left over from the last state are dropped.
+    /// Without the cache every texture is uploaded already, so there's nothing to do.
+    pub fn warm_textures(&mut self, budget_bytes: usize) {
+        let Some(ref cache) = self.texture_cache else {
+            return;
+        };
+        let mut upcoming = self.sim.graph.by_distance(self.sim.state);
+        if self.sim.graph.is_leaf(self.sim.state) {
+            // the only way on from a leaf is restarting at the egg
+            upcoming.extend(self.sim.graph.by_distance(WorldState::Egg));
         }
+        let (states, bytes) = cache.plan_warm(&upcoming, budget_bytes);
+        log_debug!(
+            "warming {:.1} MB of textures around {:?}",
+            bytes as f32 / 1e6,
+            self.sim.state
+        );
+        self.warming = states;
     }
 
-    pub fn completed(&self) -> bool {
-        self.time_progressed >= self.total_duration()
+    /// decodes the next texture of [`Self::warm_textures`], if there's one left
+    fn warm_next_texture(&mut self) {
+        if let (Some(ref cache), Some(state)) = (&self.texture_cache, self.warming.pop()) {
+            cache.get(state);
+        }
     }
 
-    /// a subsequent transition only exists for egg crack transitions, which start another crack,
-    /// or a regular transition to whatever hatches
-    pub fn subsequent_transition(&self) -> Option<Transition> {
-        use WorldState::*;
-        match self.goal_state {
-            EggCrack1 => Some(Transition::new(EggCrack2, self.t_type.clone())),
-            EggCrack2 => Some(Transition::new(
-                match self.t_type {
-                    TransitionType::EggCracking(b_type) => match b_type {
-                        ButtonType::Sun => Chick,
-                        ButtonType::Water => BabyTurtle,
-                        ButtonType::Arrowhead => panic!("arrow in egg?"),
-                        _ => panic!("restart?"),
-                    },
-                    TransitionType::Regular => panic!("transition to EggCrack2 was Regular?"),
-                },
-                TransitionType::Regular,
-            )),
-            BigEggCrack1 => Some(Transition::new(BigEggCrack2, self.t_type.clone())),
-            BigEggCrack2 => Some(Transition::new(
-                match self.t_type {
-                    TransitionType::EggCracking(b_type) => match b_type {
-                        ButtonType::Sun => SmallDragon,
-                        ButtonType::Water => Kraken,
-                        ButtonType::Arrowhead => panic!("arrow in big egg?"),
-                        _ => panic!("restart?"),
-                    },
-                    TransitionType::Regular => panic!("transition to BigEggCrack2 was Regular?"),
-                },
-                TransitionType::Regular,
-            )),
-            _ => None,
+    /// the camera to show the world on the screen with, which depends on the window size in crisp mode
+    pub fn camera(&self) -> Camera2D {
+        if !self.config.crisp {
+            return view::stretched_camera(self.config.safe_area);
+        }
+        match self.state_size(WorldState::Egg) {
+            Ok(size) => view::pixel_perfect_camera(size, self.config.safe_area),
+            Err(_) => view::stretched_camera(self.config.safe_area),
         }
     }
-}
-
-#[derive(Clone, Copy)]
-enum ButtonType {
-    Sun,
-    Water,
-    Arrowhead,
-    Restart,
-}
-
-#[derive(Clone, Copy)]
-enum ButtonState {
-    Idle,
-    Hovered,
-    Pressed,
-    Released,
-}
-
-struct Button {
-    pub b_type: ButtonType,
-    pub texture: Texture2D,
-    pub dest: Rect,
-    pub disabled: bool,
-    state: ButtonState,
-}
 
-impl Button {
-    pub async fn create() -> [Button; 4] {
-        let x_step = WORLD_WIDTH / 4.;
-        let y = 2700.;
-        let size = 600.;
-        let restart_size = 400.;
-        let border_offset = 180.;
-
-        let mut buttons = [
-            Button::new(
-                ButtonType::Sun,
-                load_texture((ASSET_PATH.to_string() + "button_sun.png").as_str())
-                    .await
-                    .unwrap(),
-                Rect::new((x_step - size / 2.) - border_offset, y, size, size),
-            ),
-            Button::new(
-                ButtonType::Water,
-                load_texture((ASSET_PATH.to_string() + "button_water.png").as_str())
-                    .await
-                    .unwrap(),
-                Rect::new(x_step * 2. - size / 2., y, size, size),
-            ),
-            Button::new(
-                ButtonType::Arrowhead,
-                load_texture((ASSET_PATH.to_string() + "button_arrow.png").as_str())
-                    .await
-                    .unwrap(),
-                Rect::new((x_step * 3. - size / 2.) + border_offset, y, size, size),
-            ),
-            Button::new(
-                ButtonType::Restart,
-                load_texture((ASSET_PATH.to_string() + "button_restart.png").as_str())
-                    .await
-                    .unwrap(),
-                Rect::new(
-                    x_step * 2. - restart_size / 2.,
-                    y + restart_size / 2.,
-                    restart_size,
-                    restart_size,
-                ),
-            ),
-        ];
+    /// Starts over from `egg`, as picked on the egg selection.
+    fn start_from(&mut self, egg: WorldState) {
+        self.sim.jump_to(egg);
+        self.discovered.insert(egg);
+        self.music.play(egg.music_track());
+    }
 
-        // restart button is disabled at the start
-        buttons[3].disable();
+    /// Fades the current state in from black, ignoring input until it's done.
+    pub fn start_intro(&mut self) {
+        self.sim.start_intro();
+    }
 
-        buttons
+    /// N/P jump straight to the next/previous state, so that every texture can be looked at in turn
+    fn handle_review_input(&mut self) {
+        let offset = if is_key_pressed(KeyCode::N) {
+            1
+        } else if is_key_pressed(KeyCode::P) {
+            -1
+        } else {
+            return;
+        };
+        self.sim.jump_to(self.sim.state.cycled(offset));
     }
 
-    fn new(b_type: ButtonType, texture: Texture2D, dest: Rect) -> Button {
-        Button {
-            b_type,
-            texture,
-            dest,
-            disabled: false,
-            state: ButtonState::Idle,
+    /// confetti and a jingle for reaching a leaf creature for the first time
+    fn celebrate(&mut self) {
+        if !self.preferences.reduced_motion {
+            self.particles.burst_confetti();
         }
+        self.play_sound(SoundIndex::Celebrate);
     }
 
-    /// updates the buttons internal state depending on the mouse and returns whether the button was clicked
-    pub fn update_button_state(&mut self, camera: &Camera2D) -> bool {
-        // start off pressed or idle, depending on whether you've been pressed in the previous frame
-        let pressed_before = matches!(self.state, ButtonState::Pressed);
-        let mut new_state = if pressed_before {
-            ButtonState::Pressed
-        } else {
-            ButtonState::Idle
-        };
+    /// Jumps to the end of the running transition (and any egg cracks it continues into),
+    /// fading out its sound instead of playing the rest of it.
+    fn skip_transition(&mut self) {
+        self.fade_out_sound();
+        let from = self.sim.state;
+        let events = self.sim.skip_transition();
+        self.handle_events(events, from);
+    }
 
-        // first get the mouse state and whether it's above you
-        let mouse_pos = mouse_world_pos(camera);
-        let mouse_pressed = macroquad::input::is_mouse_button_down(MouseButton::Left);
-        if self.dest.contains(mouse_pos) {
-            if !pressed_before {
-                if macroquad::input::is_mouse_button_pressed(MouseButton::Left) {
-                    new_state = ButtonState::Pressed;
-                } else {
-                    new_state = ButtonState::Hovered;
-                }
-            }
-            // if you have been pressed check for release
-            else if !mouse_pressed {
-                // you have been pressed down and have now been released (while over your actual position)
-                new_state = ButtonState::Released;
+    /// Stops (or starts again) everything moving on, see `--cheats`. The transition's sound is stopped,
+    /// since it would be out of step once the world goes on, and the music is silenced until then.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused == self.paused {
+            return;
+        }
+        self.paused = paused;
+        if paused {
+            // there's no fading it out either, nothing progresses while paused
+            if let Some(one_shot) = self.playing.take() {
+                audio::stop_sound(&one_shot.sound);
             }
-            // if you have been pressed, but weren't released just stay pressed (see start of function)
+            self.music.pause();
         } else {
-            // lastly make sure that releasing the button elsewhere ALSO resets your "pressed" state
-            if !mouse_pressed {
-                new_state = ButtonState::Idle;
-            }
+            self.music.resume();
         }
-        self.state = new_state;
-        let clicked = matches!(self.state, ButtonState::Released);
-        clicked
     }
 
-    /// React to mouse input, draw the button accordingly and return whether the button was clicked.
-    ///
-    /// Draws the button differently when hovered, not hovered, and pressed down.
-    pub fn draw(&self) {
-        if self.disabled {
-            return;
+    /// After the window was away (see [`frame_delta`]) the world goes on where it stopped, but the sounds
+    /// didn't stop with it, since there's no telling when the window goes. So the transition's sound that
+    /// played on is faded out now, and the music fades back in from silence, instead of both carrying on
+    /// out of step with the world.
+    fn come_back(&mut self) {
+        self.fade_out_sound();
+        self.music.fade_in_again();
+    }
+
+    fn fade_out_sound(&mut self) {
+        if let Some(ref mut one_shot) = self.playing {
+            one_shot.fade_left = one_shot.fade_left.min(SOUND_FADE_OUT);
         }
+    }
 
-        use ButtonState::*;
-        let color = match self.state {
-            Idle => Color::new(0.7, 0.7, 0.7, 1.),
-            Hovered | Released => WHITE,
-            Pressed => Color::new(0.4, 0.4, 0.4, 1.),
+    /// ramps down the volume of a fading one-shot, stopping it once it's silent
+    fn update_sound_fade(&mut self, delta_secs: f32) {
+        let Some(ref mut one_shot) = self.playing else {
+            return;
         };
+        if one_shot.fade_left == f32::INFINITY {
+            return;
+        }
+        one_shot.fade_left = (one_shot.fade_left - delta_secs).max(0.);
+        if one_shot.fade_left <= 0. {
+            audio::stop_sound(&one_shot.sound);
+            self.playing = None;
+        } else {
+            let volume = one_shot.volume * one_shot.fade_left / SOUND_FADE_OUT;
+            audio::set_sound_volume(&one_shot.sound, volume);
+        }
+    }
 
-        draw_texture_ex(
-            &self.texture,
-            self.dest.x,
-            self.dest.y,
-            color,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(self.dest.w, self.dest.h)),
-                ..Default::default()
-            },
-        );
+    /// What has to happen before the window closes: the loops are stopped and the session log is written.
+    fn shut_down(&mut self) {
+        self.stop_all_loops();
+        if let Some(ref session) = self.session {
+            session.write();
+        }
     }
 
-    pub fn disable(&mut self) {
-        self.disabled = true;
+    /// Stops every looping sound there is, so that none of them keeps playing on after quitting or
+    /// overlaps the next round. Macroquad doesn't stop them by itself, and on wasm they can outlive the page.
+    pub fn stop_all_loops(&mut self) {
+        self.music.stop();
     }
-}
 
-fn mouse_world_pos(camera: &Camera2D) -> Vec2 {
-    let mouse_screen_pos = Vec2::from(macroquad::input::mouse_position());
-    camera.screen_to_world(mouse_screen_pos)
+    /// the sound set up for revealing `state` with `--reveal-sound`, if any, faded out like a transition's
+    /// sound when the next transition is skipped
+    fn play_reveal_sound(&mut self, state: WorldState) {
+        if self.config.mute {
+            return;
+        }
+        let Some(ref sound) = self.reveal_sounds[state as usize] else {
+            return;
+        };
+        let volume = self.config.volume;
+        macroquad::audio::play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+        self.playing = Some(OneShot {
+            sound: sound.clone(),
+            volume,
+            fade_left: f32::INFINITY,
+        });
+    }
+
+    fn play_sound(&mut self, sound_index: SoundIndex) {
+        if self.config.mute {
+            return;
+        }
+        use SoundIndex::*;
+        let volume = match sound_index {
+            Scale1 | Scale2 | Scale3 | Scale4 | Scale5 => 0.7,
+            Crack1 | Crack2 => 1.1,
+            Celebrate => 1.,
+            Hover => 0.3,
+            Press => 0.5,
+        } * self.config.volume;
+        let Some(sound) = self.sound(sound_index) else {
+            return;
+        };
+        macroquad::audio::play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+        // the button sounds are too short to need fading, and mustn't take the place of a transition's sound
+        if !matches!(sound_index, Hover | Press) {
+            self.playing = Some(OneShot {
+                sound: sound.clone(),
+                volume,
+                fade_left: f32::INFINITY,
+            });
+        }
+    }
+
+    /// the image of `state`, or an error naming the state in case it hasn't been loaded
+    fn state_image(&self, state: WorldState) -> Result<StateImage, String> {
+        let index = state as usize;
+        if let Some(ref cache) = self.texture_cache {
+            return Ok(StateImage {
+                texture: cache.get(state),
+                source: None,
+            });
+        }
+        let Some((columns, rows)) = self.spritesheet_grid else {
+            let texture = self.state_textures.get(index).cloned().ok_or_else(|| {
+                format!(
+                    "no texture loaded for {:?} (expected {}{}.png)",
+                    state, ASSET_PATH, index
+                )
+            })?;
+            return Ok(StateImage {
+                texture,
+                source: None,
+            });
+        };
+        let (columns, rows) = (columns as usize, rows as usize);
+        if index >= columns * rows {
+            return Err(format!(
+                "{:?} isn't on the {}x{} spritesheet",
+                state, columns, rows
+            ));
+        }
+        let texture = self.state_textures[0].clone();
+        let cell = texture.size() / vec2(columns as f32, rows as f32);
+        let (column, row) = ((index % columns) as f32, (index / columns) as f32);
+        Ok(StateImage {
+            texture,
+            source: Some(Rect::new(column * cell.x, row * cell.y, cell.x, cell.y)),
+        })
+    }
+
+    /// the size of the image of `state` in texture pixels, without decoding it into the texture cache
+    fn state_size(&self, state: WorldState) -> Result<Vec2, String> {
+        match self.texture_cache {
+            Some(ref cache) => Ok(cache.size(state)),
+            None => self.state_image(state).map(|image| image.size()),
+        }
+    }
+
+    /// Like [`Self::state_image`], but for holding on to for a while, e.g. by a screen showing many states
+    /// at once: with `--texture-cache` it's decoded apart from the cache, so that drawing them doesn't
+    /// evict and decode over and over.
+    fn pinned_state_image(&self, state: WorldState) -> Result<StateImage, String> {
+        match self.texture_cache {
+            Some(ref cache) => Ok(StateImage {
+                texture: cache.decode(state),
+                source: None,
+            }),
+            None => self.state_image(state),
+        }
+    }
+
+    /// Every state image that gets stretched noticeably when drawn over the world, naming its file.
+    /// With `--texture-cache` the sizes come from the PNG headers, without decoding anything.
+    fn mismatched_aspect_ratios(&self) -> Vec<String> {
+        let world_ratio = WORLD_WIDTH / WORLD_HEIGHT;
+        WorldState::ALL
+            .into_iter()
+            .filter_map(|state| {
+                let size = self.state_size(state).ok()?;
+                let ratio = size.x / size.y;
+                let file = match self.spritesheet_grid {
+                    Some(_) => format!("{}{} (cell {})", ASSET_PATH, SPRITESHEET_FILE, state as usize),
+                    None => format!("{}{}.png", ASSET_PATH, state as usize),
+                };
+                ((ratio / world_ratio - 1.).abs() > ASPECT_RATIO_TOLERANCE).then(|| {
+                    format!(
+                        "{} is {}x{}, which doesn't have the world's aspect ratio of {:.3}, so {:?} gets stretched",
+                        file, size.x, size.y, world_ratio, state
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// the loaded sound for `sound_index`, if there is one
+    fn sound(&self, sound_index: SoundIndex) -> Option<&Sound> {
+        // sounds that failed to load were already reported then
+        let sound = self.sounds.get(sound_index as usize);
+        if sound.is_none() {
+            log_warn!("no sound loaded for {:?}", sound_index);
+        }
+        sound?.as_ref()
+    }
+
+    /// draws the texture of `state` at its placement, or a placeholder naming it if the texture is missing
+    fn draw_state(&self, state: WorldState, color: Color) {
+        self.draw_state_warped(state, color, Warp::IDENTITY);
+    }
+
+    /// like [`Self::draw_state`], scaled and turned around the center of its placement, e.g. in a morph
+    fn draw_state_warped(&self, state: WorldState, color: Color, warp: Warp) {
+        let color = if self.shows_shiny(state) {
+            Color::new(
+                color.r * SHINY_TINT.r,
+                color.g * SHINY_TINT.g,
+                color.b * SHINY_TINT.b,
+                color.a,
+            )
+        } else {
+            color
+        };
+        let dest = self.config.placements[state as usize].dest();
+        match self.state_image(state) {
+            Ok(image) => image.draw_warped(dest, color, warp),
+            Err(_) => draw_placeholder(state, color.a),
+        }
+    }
+
+    /// whether `state` is drawn shiny: the creatures of a shiny line, but not their egg and its cracks
+    fn shows_shiny(&self, state: WorldState) -> bool {
+        self.sim.shiny && !self.sim.graph.is_crack(state) && self.sim.graph.depth(state) != Some(0)
+    }
+
+    /// draws the texture of `state` into `dest`, or a placeholder naming it if the texture is missing
+    fn draw_state_at(&self, state: WorldState, dest: Rect, color: Color) {
+        match self.state_image(state) {
+            Ok(image) => image.draw(dest, color),
+            Err(_) => draw_placeholder(state, color.a),
+        }
+    }
+
+    fn draw_state_shadow(&self, state: WorldState, alpha: f32) {
+        if let Ok(image) = self.state_image(state) {
+            let dest = self.config.placements[state as usize].dest();
+            let params = DrawTextureParams {
+                dest_size: Some(dest.size()),
+                source: image.source,
+                ..Default::default()
+            };
+            draw_shadow(&image.texture, dest.x, dest.y, alpha, params);
+        }
+    }
+
+    /// draws the main image and after that the buttons
+    pub fn render(&self) {
+        // in case of a transition draw both images with their respecting alpha according to the transition
+        let state = self.sim.state;
+        if let Some(ref t) = self.sim.transition {
+            let (color_current, color_next) =
+                t.colors(self.render_ahead, self.preferences.reduced_motion);
+            let (warp_current, warp_next) =
+                t.warps(self.render_ahead, self.preferences.reduced_motion);
+            let from = t.from_image.unwrap_or(state);
+            // both shadows go below both creatures
+            if self.config.shadows {
+                self.draw_state_shadow(from, color_current.a);
+                self.draw_state_shadow(t.goal_state, color_next.a);
+            }
+            self.draw_state_warped(from, color_current, warp_current);
+            self.draw_state_warped(t.goal_state, color_next, warp_next);
+        } else {
+            if self.config.shadows {
+                self.draw_state_shadow(state, 1.);
+            }
+            self.draw_state(state, WHITE);
+            if self.config.review {
+                self.draw_review_label();
+            }
+        }
+        self.flash.draw();
+        if !self.config.review {
+            // still drawn while they fade out during the transition
+            for (i, button) in self.buttons.iter().enumerate() {
+                if button.visibility > 0. {
+                    let greyed_out = self.greyed_out(button.b_type);
+                    button.draw(self.focused == Some(i), self.config.shadows, greyed_out);
+                }
+            }
+            if self.sim.transition.is_none() {
+                self.draw_origin();
+                self.draw_tooltip();
+                self.draw_hint();
+                self.draw_end_note();
+                self.draw_attract_message();
+                if let Some(ref daily) = self.daily {
+                    if self.sim.graph.depth(state) == Some(0) {
+                        daily.draw(self);
+                    }
+                }
+                if self.press_time >= LONG_PRESS {
+                    self.draw_description();
+                }
+            }
+            self.combo.draw();
+            if let Some(ref speedrun) = self.speedrun {
+                speedrun.draw(self.font.as_ref());
+            }
+        }
+        self.particles.draw();
+        if let Some(ref overlay) = self.overlay {
+            overlay.draw();
+        }
+        if let Some(ref cursor) = self.cursor {
+            cursor.draw(self.mouse_pos, self.hovering_button());
+        }
+    }
+
+    /// Whether `b_type` is disabled but stays in place, greyed out. Restart is never shown greyed out,
+    /// since it takes the middle spot once it's needed, and the others make room for it then.
+    fn greyed_out(&self, b_type: ButtonType) -> bool {
+        self.config.disabled_buttons == DisabledButtons::Grey
+            && !self.sim.is_enabled(b_type)
+            && b_type != ButtonType::Restart
+            && !self.sim.is_enabled(ButtonType::Restart)
+    }
+
+    /// whether the mouse is above a button that's shown and can be clicked
+    fn hovering_button(&self) -> bool {
+        self.sim.buttons_shown()
+            && !self.config.review
+            && self
+                .buttons
+                .iter()
+                .any(|b| self.sim.is_enabled(b.b_type) && b.is_hovered())
+    }
+
+    /// Once a button has been hovered for a moment, or long pressed, names the creature it eventually leads to.
+    fn draw_tooltip(&self) {
+        let Some(button) = self
+            .buttons
+            .iter()
+            .find(|b| b.hover_time >= TOOLTIP_DELAY || b.is_long_press())
+        else {
+            return;
+        };
+        let options = self.current_options();
+        let Some(&(_, goal)) = options.iter().find(|(b_type, _)| *b_type == button.b_type) else {
+            return;
+        };
+
+        let text = goal.display_name();
+        let font_size = 110;
+        let padding = 40.;
+        let dims = measure_text(text, self.font.as_ref(), font_size, 1.);
+        let w = dims.width + 2. * padding;
+        let h = dims.height + 2. * padding;
+        // above the cursor, but never sticking out of the world
+        let x = (self.mouse_pos.x + 40.).min(WORLD_WIDTH - w).max(0.);
+        let y = (self.mouse_pos.y - h - 40.).max(0.);
+        draw_rectangle(x, y, w, h, Color::new(0., 0., 0., 0.75));
+        strings::draw_text_in(
+            self.font.as_ref(),
+            text,
+            x + padding,
+            y + padding + dims.offset_y,
+            font_size as f32,
+            WHITE,
+        );
+    }
+
+    /// the icon of the button that led to the current creature, in the top left corner for a moment after arriving
+    fn draw_origin(&self) {
+        let Some(origin) = self.sim.origin else {
+            return;
+        };
+        if self.origin_time >= ORIGIN_SHOW {
+            return;
+        }
+        let Some(button) = self.buttons.iter().find(|b| b.b_type == origin) else {
+            return;
+        };
+        let alpha = ((ORIGIN_SHOW - self.origin_time) / ORIGIN_FADE).min(1.);
+        let size = 300.;
+        draw_texture_ex(
+            &button.texture,
+            100.,
+            100.,
+            Color::new(1., 1., 1., alpha),
+            DrawTextureParams {
+                dest_size: Some(vec2(size, size)),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// the button leading to a creature that hasn't been discovered yet, if there's one from here
+    fn hinted_button(&self) -> Option<&Button> {
+        let (b_type, _) = self
+            .current_options()
+            .into_iter()
+            .find(|&(_, goal)| !self.discovered.contains(goal))?;
+        self.buttons
+            .iter()
+            .find(|b| b.b_type == b_type && b.visibility >= CLICKABLE_VISIBILITY)
+    }
+
+    /// Once nothing has happened for a while, a pulsing arrow points at a button leading somewhere new.
+    fn draw_hint(&self) {
+        if self.idle_time < self.config.hint_delay {
+            return;
+        }
+        let Some(button) = self.hinted_button() else {
+            return;
+        };
+        let time_shown = self.idle_time - self.config.hint_delay;
+        let pulse = (time_shown * HINT_PULSE_SPEED).sin() * 0.5 + 0.5;
+        let alpha = (time_shown / HINT_FADE_IN).min(1.) * (0.5 + 0.5 * pulse);
+        let size = 160.;
+        let tip = vec2(button.dest.center().x, button.dest.y - 40. - pulse * 60.);
+        draw_triangle(
+            tip,
+            tip + vec2(-size / 2., -size),
+            tip + vec2(size / 2., -size),
+            Color::new(1., 1., 1., alpha),
+        );
+    }
+
+    /// After a long while without any input at one of the eggs, a prompt to start fades in over it,
+    /// gently pulsing. Any input resets the idle time and with it the prompt. Since the idle time only
+    /// counts while [`Self::is_idle`], it waits for confetti and the like to be over too.
+    fn draw_attract_message(&self) {
+        if self.idle_time < self.config.attract_delay
+            || self.sim.graph.depth(self.sim.state) != Some(0)
+        {
+            return;
+        }
+        let Some(text) = self.strings.get(ATTRACT_KEY) else {
+            return;
+        };
+        let time_shown = self.idle_time - self.config.attract_delay;
+        let pulse = (time_shown * ATTRACT_PULSE_SPEED).sin() * 0.5 + 0.5;
+        let alpha = (time_shown / ATTRACT_FADE_IN).min(1.) * (0.6 + 0.4 * pulse);
+        let size = 180.;
+        let dims = measure_text(text, self.font.as_ref(), size as u16, 1.);
+        strings::draw_text_in(
+            self.font.as_ref(),
+            text,
+            (WORLD_WIDTH - dims.width) / 2.,
+            WORLD_HEIGHT * 0.75,
+            size,
+            Color::new(1., 1., 1., alpha),
+        );
+    }
+
+    /// In leaf states, where restarting is all that's left, says so above the restart button,
+    /// fading in after arriving.
+    fn draw_end_note(&self) {
+        if !self.sim.graph.is_leaf(self.sim.state) {
+            return;
+        }
+        let Some(text) = self.strings.get(END_NOTE_KEY) else {
+            return;
+        };
+        let Some(button) = self
+            .buttons
+            .iter()
+            .find(|b| b.b_type == ButtonType::Restart && b.visibility > 0.)
+        else {
+            return;
+        };
+        let size = 90.;
+        let alpha = (self.origin_time / END_NOTE_FADE_IN).min(1.) * button.visibility * 0.8;
+        let dims = measure_text(text, self.font.as_ref(), size as u16, 1.);
+        strings::draw_text_in(
+            self.font.as_ref(),
+            text,
+            button.dest.center().x - dims.width / 2.,
+            button.dest.y - 80.,
+            size,
+            Color::new(1., 1., 1., alpha),
+        );
+    }
+
+    /// the current creature's description, if it has one, across the top of the world
+    fn draw_description(&self) {
+        let description = self
+            .sim
+            .state
+            .description_key()
+            .and_then(|key| self.strings.get(&key));
+        if let Some(text) = description {
+            strings::draw_text_panel(
+                text,
+                self.font.as_ref(),
+                200.,
+                200.,
+                WORLD_WIDTH - 400.,
+                110,
+            );
+        }
+    }
+
+    /// names the current state and its texture index, so reviewers can point at the exact asset
+    fn draw_review_label(&self) {
+        strings::draw_text_in(
+            self.font.as_ref(),
+            format!(
+                "{}/{}: {:?} ({}.png)",
+                self.sim.state as usize + 1,
+                WORLD_STATE_VARIANTS,
+                self.sim.state,
+                self.sim.state as usize
+            )
+            .as_str(),
+            60.,
+            180.,
+            140.,
+            WHITE,
+        );
+    }
+}
+
+/// a sound effect that was started, kept around to be able to fade it out
+struct OneShot {
+    sound: Sound,
+    volume: f32,
+    /// seconds until it's silent, infinite while it isn't fading
+    fade_left: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ButtonType {
+    Sun,
+    Water,
+    Arrowhead,
+    Restart,
+}
+
+impl ButtonType {
+    pub const ALL: [ButtonType; 4] = [
+        ButtonType::Sun,
+        ButtonType::Water,
+        ButtonType::Arrowhead,
+        ButtonType::Restart,
+    ];
+
+    pub fn from_name(name: &str) -> Option<ButtonType> {
+        match name.to_ascii_lowercase().as_str() {
+            "sun" => Some(ButtonType::Sun),
+            "water" => Some(ButtonType::Water),
+            "arrow" | "arrowhead" => Some(ButtonType::Arrowhead),
+            "restart" => Some(ButtonType::Restart),
+            _ => None,
+        }
+    }
+}
+
+/// what happens to the buttons that don't lead anywhere from the current state
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DisabledButtons {
+    /// they fade out
+    Hide,
+    /// they stay in place, faint and grey, so the layout doesn't change
+    Grey,
+}
+
+impl DisabledButtons {
+    pub fn from_name(name: &str) -> Option<DisabledButtons> {
+        match name.to_ascii_lowercase().as_str() {
+            "hide" => Some(DisabledButtons::Hide),
+            "grey" | "gray" => Some(DisabledButtons::Grey),
+            _ => None,
+        }
+    }
+}
+
+/// where the buttons are placed in the world
+#[derive(Clone, Copy)]
+enum ButtonLayout {
+    /// side by side along the bottom
+    Row,
+    /// stacked along the right side, for wide displays
+    Column,
+}
+
+impl ButtonLayout {
+    pub fn from_name(name: &str) -> Option<ButtonLayout> {
+        match name.to_ascii_lowercase().as_str() {
+            "row" => Some(ButtonLayout::Row),
+            "column" => Some(ButtonLayout::Column),
+            _ => None,
+        }
+    }
+
+    /// The rects of the sun, water, arrowhead and restart buttons, in that order.
+    ///
+    /// Restart only ever shows up once the others are gone, so it takes the middle spot.
+    fn dests(&self) -> [Rect; 4] {
+        let size = 600.;
+        let restart_size = 400.;
+        match self {
+            ButtonLayout::Row => {
+                let x_step = WORLD_WIDTH / 4.;
+                let y = 2700.;
+                let border_offset = 180.;
+                [
+                    Rect::new((x_step - size / 2.) - border_offset, y, size, size),
+                    Rect::new(x_step * 2. - size / 2., y, size, size),
+                    Rect::new((x_step * 3. - size / 2.) + border_offset, y, size, size),
+                    Rect::new(
+                        x_step * 2. - restart_size / 2.,
+                        y + restart_size / 2.,
+                        restart_size,
+                        restart_size,
+                    ),
+                ]
+            }
+            ButtonLayout::Column => {
+                let y_step = WORLD_HEIGHT / 4.;
+                let x = WORLD_WIDTH - size - 120.;
+                [
+                    Rect::new(x, y_step - size / 2., size, size),
+                    Rect::new(x, y_step * 2. - size / 2., size, size),
+                    Rect::new(x, y_step * 3. - size / 2., size, size),
+                    Rect::new(
+                        x + (size - restart_size) / 2.,
+                        y_step * 2. - restart_size / 2.,
+                        restart_size,
+                        restart_size,
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ButtonState {
+    Idle,
+    Hovered,
+    Pressed,
+    Released,
+}
+
+impl ButtonState {
+    /// what a button is tinted with in this state, darker when it's idle and darkest when pressed down
+    fn tint(self) -> Color {
+        use ButtonState::*;
+        match self {
+            Idle => Color::new(0.7, 0.7, 0.7, 1.),
+            Hovered | Released => WHITE,
+            Pressed => Color::new(0.4, 0.4, 0.4, 1.),
+        }
+    }
+
+    /// The state after this one, given the pointer this frame and whether it's `over` the button.
+    /// A click is a press and a release both over the button, leaving it in between is fine as long as
+    /// the pointer comes back before it's released.
+    fn next(self, pointer: &Pointer, over: bool) -> ButtonState {
+        if pointer.cancelled {
+            return ButtonState::Idle;
+        }
+        // start off pressed or idle, depending on whether you've been pressed in the previous frame
+        let pressed_before = matches!(self, ButtonState::Pressed);
+        if over {
+            if !pressed_before {
+                if pointer.just_pressed {
+                    ButtonState::Pressed
+                } else {
+                    ButtonState::Hovered
+                }
+            }
+            // you have been pressed down and have now been released (while over your actual position)
+            else if !pointer.down {
+                ButtonState::Released
+            }
+            // if you have been pressed, but weren't released just stay pressed
+            else {
+                ButtonState::Pressed
+            }
+        }
+        // releasing the button elsewhere ALSO resets your "pressed" state
+        else if pressed_before && pointer.down {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Idle
+        }
+    }
+}
+
+/// What changed about a button from one frame to the next, for reacting to it exactly once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ButtonEvent {
+    /// the pointer moved onto it
+    Entered,
+    /// the pointer moved off it, or a press was let go of or cancelled elsewhere
+    Left,
+    /// pressed down, whether the pointer was on it already or not
+    Pressed,
+    /// pressed and released on it
+    Clicked,
+    /// held down on it for long enough to count as a long press, which won't click once it's released
+    LongPressed,
+}
+
+impl ButtonEvent {
+    /// the event going from `before` to `after`, if anything happened
+    fn between(before: ButtonState, after: ButtonState) -> Option<ButtonEvent> {
+        use ButtonState::*;
+        match (before, after) {
+            (_, Released) => Some(ButtonEvent::Clicked),
+            (Idle | Hovered | Released, Pressed) => Some(ButtonEvent::Pressed),
+            (Idle, Hovered) => Some(ButtonEvent::Entered),
+            (Hovered | Pressed | Released, Idle) => Some(ButtonEvent::Left),
+            _ => None,
+        }
+    }
+}
+
+/// The mouse or a finger, whichever is in use, in world coordinates.
+struct Pointer {
+    pos: Vec2,
+    down: bool,
+    /// went down this frame
+    just_pressed: bool,
+    /// the system took the touch away (e.g. for a gesture), which never counts as a release
+    cancelled: bool,
+    /// a finger rather than the mouse
+    touch: bool,
+}
+
+impl Pointer {
+    /// The first finger on the screen if there is one, the mouse otherwise.
+    ///
+    /// Touches are read directly rather than through macroquad's mouse emulation,
+    /// which never lets go of the emulated button when a touch is cancelled.
+    fn current(camera: &Camera2D) -> Pointer {
+        let Some(touch) = touches().into_iter().min_by_key(|touch| touch.id) else {
+            return Pointer {
+                pos: mouse_world_pos(camera),
+                down: is_mouse_button_down(MouseButton::Left),
+                just_pressed: is_mouse_button_pressed(MouseButton::Left),
+                cancelled: false,
+                touch: false,
+            };
+        };
+        Pointer {
+            pos: camera.screen_to_world(touch.position),
+            down: matches!(
+                touch.phase,
+                TouchPhase::Started | TouchPhase::Moved | TouchPhase::Stationary
+            ),
+            just_pressed: touch.phase == TouchPhase::Started,
+            cancelled: touch.phase == TouchPhase::Cancelled,
+            touch: true,
+        }
+    }
+}
+
+struct Button {
+    pub b_type: ButtonType,
+    pub texture: Texture2D,
+    /// where the button is drawn and clicked, `layout_dest` grown to the minimum tap size if needed
+    pub dest: Rect,
+    /// where the layout puts the button
+    layout_dest: Rect,
+    state: ButtonState,
+    /// for how long the button has been hovered without interruption, in seconds
+    hover_time: f32,
+    /// 0 is invisible, 1 fully visible, fading towards whether the button is shown
+    visibility: f32,
+    /// the color the texture is tinted with, fading towards the one of the current state
+    tint: Color,
+    /// seconds pressed down with the pointer over the button, 0 unless it's pressed
+    press_time: f32,
+    /// how many seconds of `press_time` make a long press, which peeks instead of clicking
+    long_press: Option<f32>,
+    /// how far the pointer has gotten from where the current press started, at most
+    press_travel: f32,
+    press_pos: Vec2,
+    taps: TapThresholds,
+}
+
+impl Button {
+    pub async fn create(layout: ButtonLayout) -> [Button; 4] {
+        let [sun, water, arrow, restart] = layout.dests();
+        [
+            Button::new(
+                ButtonType::Sun,
+                Self::load_button_texture("button_sun.png").await,
+                sun,
+            ),
+            Button::new(
+                ButtonType::Water,
+                Self::load_button_texture("button_water.png").await,
+                water,
+            ),
+            Button::new(
+                ButtonType::Arrowhead,
+                Self::load_button_texture("button_arrow.png").await,
+                arrow,
+            ),
+            Button::new(
+                ButtonType::Restart,
+                Self::load_button_texture("button_restart.png").await,
+                restart,
+            ),
+        ]
+    }
+
+    /// the texture in `file` under `ASSET_PATH`, the game can't do without any of them
+    async fn load_button_texture(file: &str) -> Texture2D {
+        let path = format!("{}{}", ASSET_PATH, file);
+        required(load_texture(&path).await, &path)
+    }
+
+    fn new(b_type: ButtonType, texture: Texture2D, dest: Rect) -> Button {
+        Button {
+            b_type,
+            texture,
+            dest,
+            layout_dest: dest,
+            state: ButtonState::Idle,
+            hover_time: 0.,
+            visibility: 0.,
+            tint: ButtonState::Idle.tint(),
+            press_time: 0.,
+            long_press: None,
+            press_travel: 0.,
+            press_pos: Vec2::ZERO,
+            taps: TapThresholds::default(),
+        }
+    }
+
+    /// A button without a real texture, which can be built without a window, for testing its input handling.
+    #[cfg(test)]
+    fn new_for_test(b_type: ButtonType, dest: Rect) -> Button {
+        use macroquad::miniquad::{RawId, TextureId};
+        let texture = Texture2D::from_miniquad_texture(TextureId::from_raw_id(RawId::OpenGl(0)));
+        Button::new(b_type, texture, dest)
+    }
+
+    /// Grows the button around its center until it's at least `min_size` pixels wide and high on screen,
+    /// or shrinks it back to its layout size once that's big enough again.
+    pub fn fit_to_tap_size(&mut self, min_size: f32, pixels_per_unit: f32) {
+        let on_screen = self.layout_dest.w.min(self.layout_dest.h) * pixels_per_unit;
+        let scale = (min_size / on_screen).max(1.);
+        let (w, h) = (self.layout_dest.w * scale, self.layout_dest.h * scale);
+        let center = self.layout_dest.center();
+        self.dest = Rect::new(center.x - w / 2., center.y - h / 2., w, h);
+    }
+
+    /// Fades the button in if it's `shown`, out otherwise, and its tint towards the color of its state,
+    /// which `reduced_motion` switches to right away instead.
+    pub fn update_anim(&mut self, delta_secs: f32, shown: bool, reduced_motion: bool) {
+        let step = delta_secs / BUTTON_FADE;
+        self.visibility = if shown {
+            (self.visibility + step).min(1.)
+        } else {
+            (self.visibility - step).max(0.)
+        };
+        let target = self.state.tint();
+        self.tint = if reduced_motion {
+            target
+        } else {
+            let amount = (delta_secs / BUTTON_TINT_FADE).min(1.);
+            Color::from_vec(self.tint.to_vec().lerp(target.to_vec(), amount))
+        };
+    }
+
+    /// whether it's on its way between shown and hidden
+    fn is_fading(&self) -> bool {
+        self.visibility > 0. && self.visibility < 1.
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        matches!(self.state, ButtonState::Hovered | ButtonState::Pressed)
+    }
+
+    /// Updates the buttons internal state depending on the pointer and returns what happened to it, if anything.
+    ///
+    /// A button that isn't `clickable` (disabled or not shown) goes back to idle, so that once it is again,
+    /// only a fresh press counts, not one still held from before, e.g. through a whole transition.
+    /// Once a press is held long enough to be a long press, letting go of it doesn't click anymore,
+    /// and neither does one that's too short or has moved too far for the `taps` thresholds.
+    pub fn update_button_state(
+        &mut self,
+        pointer: &Pointer,
+        delta_secs: f32,
+        clickable: bool,
+    ) -> Option<ButtonEvent> {
+        let before = self.state;
+        let was_long_press = self.is_long_press();
+        let over = self.dest.contains(pointer.pos);
+        self.state = if clickable {
+            self.state.next(pointer, over)
+        } else {
+            ButtonState::Idle
+        };
+        if before != ButtonState::Pressed && self.state == ButtonState::Pressed {
+            self.press_pos = pointer.pos;
+        }
+        if self.state == ButtonState::Pressed {
+            self.press_travel = self.press_travel.max(pointer.pos.distance(self.press_pos));
+        }
+        if self.state == ButtonState::Released && (was_long_press || self.is_misfire(pointer)) {
+            self.state = ButtonState::Hovered;
+        }
+        if self.state != ButtonState::Pressed {
+            self.press_time = 0.;
+            self.press_travel = 0.;
+        } else if over {
+            self.press_time += delta_secs;
+        }
+        self.hover_time = if matches!(self.state, ButtonState::Hovered) {
+            self.hover_time + delta_secs
+        } else {
+            0.
+        };
+        if !was_long_press && self.is_long_press() {
+            return Some(ButtonEvent::LongPressed);
+        }
+        ButtonEvent::between(before, self.state)
+    }
+
+    /// whether the press that was just let go of was too short, or moved too far, to be a click
+    fn is_misfire(&self, pointer: &Pointer) -> bool {
+        let tap = if pointer.touch {
+            self.taps.touch
+        } else {
+            self.taps.mouse
+        };
+        self.press_time < tap.min_press || self.press_travel > tap.max_travel
+    }
+
+    /// whether it's been held down long enough to peek at where it leads
+    fn is_long_press(&self) -> bool {
+        self.long_press
+            .is_some_and(|after| self.state == ButtonState::Pressed && self.press_time >= after)
+    }
+
+    /// React to mouse input, draw the button accordingly and return whether the button was clicked.
+    ///
+    /// Draws the button differently when hovered, not hovered, and pressed down.
+    /// A button with keyboard focus gets a ring around it, a `greyed_out` one is drawn faint and grey.
+    pub fn draw(&self, focused: bool, shadow: bool, greyed_out: bool) {
+        let mut color = if greyed_out {
+            Color::new(0.5, 0.5, 0.5, 1.)
+        } else {
+            self.tint
+        };
+        // eased, so that the fade starts and ends softly
+        let v = self.visibility;
+        color.a = v * v * (3. - 2. * v);
+        if greyed_out {
+            color.a *= GREYED_OUT_ALPHA;
+        }
+
+        let params = DrawTextureParams {
+            dest_size: Some(Vec2::new(self.dest.w, self.dest.h)),
+            ..Default::default()
+        };
+        if shadow {
+            draw_shadow(
+                &self.texture,
+                self.dest.x,
+                self.dest.y,
+                color.a,
+                params.clone(),
+            );
+        }
+        draw_texture_ex(&self.texture, self.dest.x, self.dest.y, color, params);
+
+        if focused {
+            let margin = 30.;
+            draw_rectangle_lines(
+                self.dest.x - margin,
+                self.dest.y - margin,
+                self.dest.w + 2. * margin,
+                self.dest.h + 2. * margin,
+                16.,
+                Color {
+                    a: color.a,
+                    ..WHITE
+                },
+            );
+        }
+    }
+
+    /// The clickable area with the button's type, drawn for disabled buttons too, for checking the layout.
+    pub fn draw_hitbox(&self, enabled: bool) {
+        let color = if enabled { GREEN } else { RED };
+        draw_rectangle_lines(
+            self.dest.x,
+            self.dest.y,
+            self.dest.w,
+            self.dest.h,
+            8.,
+            color,
+        );
+        let label = format!(
+            "{:?}{}",
+            self.b_type,
+            if enabled { "" } else { " (disabled)" }
+        );
+        draw_text(&label, self.dest.x + 20., self.dest.y + 70., 60., color);
+    }
+}
+
+/// The texture a state is drawn from, and which part of it is the state's if they share a spritesheet.
+struct StateImage {
+    texture: Texture2D,
+    source: Option<Rect>,
+}
+
+impl StateImage {
+    /// in texture pixels
+    fn size(&self) -> Vec2 {
+        self.source
+            .map_or_else(|| self.texture.size(), |source| source.size())
+    }
+
+    fn draw(&self, dest: Rect, color: Color) {
+        self.draw_warped(dest, color, Warp::IDENTITY);
+    }
+
+    /// drawn into `dest` scaled by the warp around its center, and turned around it as well
+    fn draw_warped(&self, dest: Rect, color: Color, warp: Warp) {
+        let size = dest.size() * warp.scale;
+        let pos = dest.center() - size / 2.;
+        let params = DrawTextureParams {
+            dest_size: Some(size),
+            source: self.source,
+            rotation: warp.rotation,
+            ..Default::default()
+        };
+        draw_texture_ex(&self.texture, pos.x, pos.y, color, params);
+    }
+}
+
+/// Stands in for a state whose texture is missing, so that rendering can go on.
+fn draw_placeholder(state: WorldState, alpha: f32) {
+    draw_rectangle(
+        0.,
+        0.,
+        WORLD_WIDTH,
+        WORLD_HEIGHT,
+        Color::new(0.3, 0., 0.3, alpha),
+    );
+    draw_text(
+        format!("missing texture: {:?}", state).as_str(),
+        100.,
+        WORLD_HEIGHT / 2.,
+        140.,
+        Color::new(1., 1., 1., alpha),
+    );
+}
+
+/// Draws a blackened copy of the texture, shifted by `SHADOW_OFFSET`, to be drawn just before the texture itself.
+/// `alpha` is the alpha the texture is drawn with, so that the shadow fades with it.
+fn draw_shadow(texture: &Texture2D, x: f32, y: f32, alpha: f32, params: DrawTextureParams) {
+    draw_texture_ex(
+        texture,
+        x + SHADOW_OFFSET,
+        y + SHADOW_OFFSET,
+        Color::new(0., 0., 0., SHADOW_ALPHA * alpha),
+        params,
+    );
+}
+
+/// roughly how much GPU memory `texture` takes, at 4 bytes per texel
+fn texture_bytes(texture: &Texture2D) -> usize {
+    texture.width() as usize * texture.height() as usize * 4
+}
+
+/// the world always moves on by this much at a time, however fast the frames come, see [`FixedTimestep`]
+const FIXED_STEP: f32 = 1. / 120.;
+
+/// The full-screen flash as each egg crack is done, with `--crack-flash`. Without it, it never starts.
+struct CrackFlash {
+    config: Option<Flash>,
+    /// how much of the flash is left, from 1 as a crack is done down to 0
+    left: f32,
+}
+
+impl CrackFlash {
+    fn new(config: Option<Flash>) -> Self {
+        Self { config, left: 0. }
+    }
+
+    fn start(&mut self) {
+        if self.config.is_some() {
+            self.left = 1.;
+        }
+    }
+
+    fn update(&mut self, delta_secs: f32) {
+        if let Some(flash) = self.config {
+            self.left = (self.left - delta_secs / flash.duration).max(0.);
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.left <= 0.
+    }
+
+    /// over the whole world, fading out quickly
+    fn draw(&self) {
+        let Some(flash) = self.config.filter(|_| !self.is_over()) else {
+            return;
+        };
+        let mut color = flash.color;
+        color.a = flash.intensity * self.left * self.left;
+        draw_rectangle(0., 0., WORLD_WIDTH, WORLD_HEIGHT, color);
+    }
+}
+
+/// Turns the frame times into a whole number of fixed steps, carrying what's left over into the next frame,
+/// so that everything moves at the same pace on a 60 Hz and a 144 Hz display. Rendering looks ahead by
+/// what's left over, so that the crossfades stay smooth even when a frame gets no step at all.
+#[derive(Default)]
+struct FixedTimestep {
+    /// seconds that haven't been stepped yet, less than one step
+    left_over: f32,
+}
+
+impl FixedTimestep {
+    /// how many steps of `FIXED_STEP` the frame gets after `delta` seconds
+    fn steps(&mut self, delta: f32) -> usize {
+        self.left_over += delta;
+        let steps = (self.left_over / FIXED_STEP).floor();
+        self.left_over -= steps * FIXED_STEP;
+        steps as usize
+    }
+}
+
+/// The time the last frame took, or None if the window was away for longer than `MAX_FRAME_DELTA`.
+///
+/// Minimized windows and hidden browser tabs stop getting frames, and macroquad doesn't say when
+/// that happens, so the long frame after coming back is the only sign of it. The world then goes on
+/// from where it stopped, instead of jumping ahead by all the time it was away, see [`World::come_back`].
+fn frame_delta() -> Option<f32> {
+    let delta = get_frame_time();
+    if delta > MAX_FRAME_DELTA {
+        log_info!(
+            "the window was away for {:.1}s, resuming where it stopped",
+            delta
+        );
+        return None;
+    }
+    Some(delta)
+}
+
+/// Whether the window has been asked to close. Macroquad only reports that in the frame it happens in,
+/// so it's remembered here for the menus with frame loops of their own, to return early and leave it to `run`.
+fn quit_requested() -> bool {
+    static REQUESTED: AtomicBool = AtomicBool::new(false);
+    if is_quit_requested() {
+        REQUESTED.store(true, Ordering::Relaxed);
+    }
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+fn mouse_world_pos(camera: &Camera2D) -> Vec2 {
+    let mouse_screen_pos = Vec2::from(macroquad::input::mouse_position());
+    camera.screen_to_world(mouse_screen_pos)
+}
+
+/// With `--choose-egg`, lets the player pick the egg to start with, unless the profile continues somewhere further.
+async fn select_egg_if_at_start(world: &mut World, cam: &Camera2D) {
+    if !world.config.choose_egg || !matches!(world.sim.state, WorldState::Egg | WorldState::BigEgg)
+    {
+        return;
+    }
+    if let Some(egg) = egg_selection::select_egg(cam, world).await {
+        world.start_from(egg);
+    }
 }
 
-fn get_window_conf() -> macroquad::window::Conf {
+fn get_window_conf(config: &Config) -> macroquad::window::Conf {
     macroquad::window::Conf {
         // I just like it when things are blurry...
         high_dpi: true,
         window_width: (WORLD_WIDTH / 6.) as i32,
         window_height: (WORLD_HEIGHT / 6.) as i32,
+        platform: macroquad::miniquad::conf::Platform {
+            framebuffer_alpha: config.clear_color.a < 1.,
+            ..Default::default()
+        },
+        sample_count: config.msaa,
         ..Default::default()
     }
 }
 
-#[macroquad::main(get_window_conf)]
-async fn main() {
+/// GL_SAMPLES, which isn't among the constants miniquad exports
+#[cfg(not(target_arch = "wasm32"))]
+const GL_SAMPLES: u32 = 0x80A9;
+
+/// Warns if the window got fewer MSAA samples than `requested`. Most platforms hand out the closest
+/// they support instead of failing, which is fine to run with, but worth knowing about.
+#[cfg(not(target_arch = "wasm32"))]
+fn check_msaa(requested: i32) {
+    if requested <= 1 {
+        return;
+    }
+    let mut samples = 0;
+    // the window's context is current once `run` is called
+    unsafe { macroquad::miniquad::gl::glGetIntegerv(GL_SAMPLES, &mut samples) };
+    if samples < requested {
+        log_warn!(
+            "asked for {} MSAA samples, but the window only got {}",
+            requested,
+            samples.max(1)
+        );
+    } else {
+        log_info!("running with {} MSAA samples", samples);
+    }
+}
+
+/// Whether the asset directory is there at all. On the web the assets are fetched from the server,
+/// so that can't be told up front.
+fn assets_found() -> bool {
+    cfg!(target_arch = "wasm32") || std::path::Path::new(ASSET_PATH).is_dir()
+}
+
+/// Shows `message` in place of the loading screen until the window is closed or escape is pressed,
+/// for when the game can't start at all.
+async fn show_fatal_error(config: &Config, message: &str) {
+    let size = 100.;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            return;
+        }
+        clear_background(config.loading_background());
+        let dims = measure_text(message, None, size as u16, 1.);
+        draw_text(
+            message,
+            (WORLD_WIDTH - dims.width) / 2.,
+            (WORLD_HEIGHT + dims.offset_y) / 2.,
+            size,
+            config.loading_screen.text_color,
+        );
+        next_frame().await;
+    }
+}
+
+fn main() {
+    let config = Config::from_args();
+    // dumping the graph doesn't need a window, so it happens before there is one
+    if let Some(ref path) = config.dump_graph {
+        match std::fs::write(path, StateGraph::default().to_dot()) {
+            Ok(()) => log_info!("wrote the state graph to {}", path),
+            Err(e) => log_error!("couldn't write the state graph to {}: {}", path, e),
+        }
+        return;
+    }
+    macroquad::Window::from_config(get_window_conf(&config), run(config));
+}
+
+async fn run(config: Config) {
     // start of with a loading screen
-    let mut cam = Camera2D::from_display_rect(Rect::new(0., 0., WORLD_WIDTH, WORLD_HEIGHT));
-    cam.zoom = Vec2::new(cam.zoom.x, -cam.zoom.y); // workaround for https://github.com/not-fl3/macroquad/issues/171
+    let mut cam = view::stretched_camera(config.safe_area);
     set_camera(&cam);
+    #[cfg(not(target_arch = "wasm32"))]
+    check_msaa(config.msaa);
+
+    if !assets_found() {
+        let message = format!(
+            "{} not found, run from the project root",
+            ASSET_PATH.trim_end_matches('/')
+        );
+        log_error!("{}", message);
+        show_fatal_error(&config, &message).await;
+        return;
+    }
 
     // LOADING
+    let loading_until = get_time() + config.min_loading_time as f64;
+    let font = strings::load_font().await;
+    let world_font = font.clone();
+    let look = config.loading_screen.clone();
+    let background = config.loading_background();
+    // centered as if all the dots were there, so that the text doesn't move while they come and go
+    let text_pos = look.text_pos.unwrap_or_else(|| {
+        let dims = measure_text("Loading...", font.as_ref(), look.text_size as u16, 1.);
+        vec2(
+            (WORLD_WIDTH - dims.width) / 2.,
+            (WORLD_HEIGHT + dims.offset_y) / 2.,
+        )
+    });
     let world_loading = start_coroutine(async move {
-        storage::store(World::new().await);
+        storage::store(World::new(config, world_font).await);
     });
-    while !world_loading.is_done() {
-        clear_background(Color::default());
+    while !world_loading.is_done() || get_time() < loading_until {
+        clear_background(background);
         let secs = get_time();
         let dots = ".".repeat(secs as usize % 4);
-        draw_text(
+        strings::draw_text_in(
+            font.as_ref(),
             format!("Loading{}", dots).as_str(),
-            760.,
-            1600.,
-            200.,
-            WHITE,
+            text_pos.x,
+            text_pos.y,
+            look.text_size,
+            look.text_color,
         );
 
         next_frame().await;
     }
 
     let mut world = storage::get_mut::<World>();
+    if let Some(budget) = world.config.texture_budget {
+        world.warm_textures(budget);
+    }
+    cam = world.camera();
+
+    let mismatches = world.mismatched_aspect_ratios();
+    for mismatch in mismatches.iter() {
+        log_warn!("{}", mismatch);
+    }
+    if world.config.validate_assets {
+        if mismatches.is_empty() {
+            println!("every state texture has the world's aspect ratio");
+            return;
+        }
+        std::process::exit(1);
+    }
+
+    if world.config.contact_sheet {
+        export::export_contact_sheet(&world);
+        return;
+    }
+    if let Some(frames) = world.config.bench_render {
+        export::bench_render(&mut world, frames);
+        return;
+    }
+    if let Some((from, b_type)) = world.config.export_transition {
+        export::export_transition(&mut world, from, b_type);
+        return;
+    }
+
+    // the session log is written and the loops are stopped on the way out, so quitting has to wait for it,
+    // the menus hand it back to here through quit_requested
+    prevent_quit();
+    if !world.config.review {
+        let profile = match world.config.profile.clone() {
+            Some(profile) => profile,
+            None => match profile::select_profile(&cam).await {
+                Some(profile) => profile,
+                None => return world.shut_down(),
+            },
+        };
+        world.switch_profile(profile);
+        select_egg_if_at_start(&mut world, &cam).await;
+        world.start_intro();
+    }
 
+    if let Some(path) = world.config.script.clone() {
+        match script::load(&path).await {
+            Ok(player) => world.script = Some(player),
+            Err(e) => e.exit(),
+        }
+    }
+
+    let mut tween = view::CameraTween::new();
+    let mut timestep = FixedTimestep::default();
     loop {
-        clear_background(Color::default());
+        if quit_requested() {
+            return world.shut_down();
+        }
+        clear_background(world.config.clear_color);
 
+        let delta = frame_delta().unwrap_or_else(|| {
+            world.come_back();
+            0.
+        });
+        // input is hit-tested through the same eased camera that's shown
+        cam = tween.update(world.camera(), delta, world.preferences.reduced_motion);
         set_camera(&cam);
 
-        world.handle_input(&cam);
+        // F2 (unless rebound) goes back to the profile selection, F1 opens the settings
+        if world.bindings.pressed(Action::SelectProfile) && world.profile.is_some() {
+            if let Some(profile) = profile::select_profile(&cam).await {
+                world.switch_profile(profile);
+                select_egg_if_at_start(&mut world, &cam).await;
+            }
+        }
+        if world.bindings.pressed(Action::Gallery) {
+            gallery::gallery(&cam, &world).await;
+        }
+        if world.bindings.pressed(Action::DumpState) {
+            world.write_dump();
+        }
+        if world.bindings.pressed(Action::EventLog) {
+            world.event_log.shown = !world.event_log.shown;
+        }
+        world.update_event_log();
+        if is_key_pressed(KeyCode::F1) {
+            let bindings = world.bindings.clone();
+            let preferences = world.preferences.clone();
+            (world.bindings, world.preferences) =
+                settings::settings_menu(&cam, bindings, preferences).await;
+        }
 
-        let delta = get_frame_time();
-        world.progress(delta);
+        if world.config.cheats {
+            world.handle_cheat_input();
+        }
+        world.handle_input(&cam, delta);
+        world.run_script(delta);
+        for _ in 0..timestep.steps(delta) {
+            world.progress(FIXED_STEP);
+        }
+        // once per rendered frame rather than per step, so that the decoding stays spread out
+        if world.is_idle() {
+            world.warm_next_texture();
+        }
+        world.render_ahead = if world.paused {
+            0.
+        } else {
+            timestep.left_over * world.current_transition_speed()
+        };
 
         world.render();
+        world.draw_debug_overlay();
+        world.draw_event_log();
 
         set_default_camera();
 
-        // draw_text(format!("FPS: {}", get_fps()).as_str(), 0., 16., 32., WHITE);
-        // draw_text(
-        //     format!("width: {}", screen_width()).as_str(),
-        //     0.,
-        //     32.,
-        //     32.,
-        //     WHITE,
-        // );
-        // draw_text(
-        //     format!("height: {}", screen_height()).as_str(),
-        //     0.,
-        //     48.,
-        //     32.,
-        //     WHITE,
-        // );
-
         next_frame().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a pointer for one frame of a press, `down` is whether it's still held afterwards
+    fn pointer(down: bool, just_pressed: bool) -> Pointer {
+        Pointer {
+            pos: Vec2::ZERO,
+            down,
+            just_pressed,
+            cancelled: false,
+            touch: false,
+        }
+    }
+
+    /// the state after each frame, given whether the pointer is over the button and held then
+    fn run_button(frames: &[(bool, bool)]) -> Vec<ButtonState> {
+        let mut state = ButtonState::Idle;
+        let mut down_before = false;
+        frames
+            .iter()
+            .map(|&(over, down)| {
+                state = state.next(&pointer(down, down && !down_before), over);
+                down_before = down;
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn press_and_release_over_the_button_clicks() {
+        use ButtonState::*;
+        let states = run_button(&[(true, false), (true, true), (true, true), (true, false)]);
+        assert_eq!(states, [Hovered, Pressed, Pressed, Released]);
+    }
+
+    #[test]
+    fn dragging_off_and_releasing_cancels() {
+        use ButtonState::*;
+        let states = run_button(&[(true, true), (false, true), (false, false), (true, false)]);
+        assert_eq!(states, [Pressed, Pressed, Idle, Hovered]);
+    }
+
+    #[test]
+    fn dragging_back_before_releasing_still_clicks() {
+        use ButtonState::*;
+        let states = run_button(&[(true, true), (false, true), (true, true), (true, false)]);
+        assert_eq!(states, [Pressed, Pressed, Pressed, Released]);
+    }
+
+    #[test]
+    fn pressing_elsewhere_and_dragging_onto_the_button_doesnt_click() {
+        use ButtonState::*;
+        let states = run_button(&[(false, true), (true, true), (true, false)]);
+        assert_eq!(states, [Idle, Hovered, Hovered]);
+    }
+
+    #[test]
+    fn cancelled_touches_never_click() {
+        let cancelled = Pointer {
+            cancelled: true,
+            ..pointer(false, false)
+        };
+        assert_eq!(
+            ButtonState::Pressed.next(&cancelled, true),
+            ButtonState::Idle
+        );
+    }
+
+    /// whether `button` was clicked in each frame, given where the pointer was and whether it was held then,
+    /// with the button clickable throughout
+    fn clicks_at(button: &mut Button, frames: &[(Vec2, bool)]) -> Vec<bool> {
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|&(pos, down)| (pos, down, true))
+            .collect();
+        clicks_while(button, &frames)
+    }
+
+    /// like [`clicks_at`], with whether the button was clickable in each frame too
+    fn clicks_while(button: &mut Button, frames: &[(Vec2, bool, bool)]) -> Vec<bool> {
+        let mut down_before = false;
+        frames
+            .iter()
+            .map(|&(pos, down, clickable)| {
+                let pointer = Pointer {
+                    pos,
+                    ..pointer(down, down && !down_before)
+                };
+                down_before = down;
+                button.update_button_state(&pointer, 1. / 60., clickable)
+                    == Some(ButtonEvent::Clicked)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn long_presses_peek_instead_of_clicking() {
+        let mut button = Button::new_for_test(ButtonType::Sun, Rect::new(0., 0., 100., 100.));
+        button.long_press = Some(0.1);
+        let inside = vec2(50., 50.);
+        let mut held = |frames: usize| {
+            let mut events = Vec::new();
+            for frame in 0..=frames {
+                let down = frame < frames;
+                let pointer = Pointer {
+                    pos: inside,
+                    ..pointer(down, down && frame == 0)
+                };
+                events.extend(button.update_button_state(&pointer, 1. / 60., true));
+            }
+            events
+        };
+        // a tap of a few frames still clicks
+        assert_eq!(held(3), [ButtonEvent::Pressed, ButtonEvent::Clicked]);
+        // holding for 0.2s goes past the threshold once, and letting go doesn't click anymore
+        assert_eq!(held(12), [ButtonEvent::Pressed, ButtonEvent::LongPressed]);
+    }
+
+    #[test]
+    fn taps_below_the_threshold_dont_click() {
+        let mut button = Button::new_for_test(ButtonType::Sun, Rect::new(0., 0., 100., 100.));
+        button.taps.mouse = config::TapThreshold {
+            min_press: 0.05,
+            max_travel: 10.,
+        };
+        let (inside, nearby, far) = (vec2(50., 50.), vec2(55., 50.), vec2(80., 50.));
+        let tap = |pos: &[Vec2]| -> Vec<(Vec2, bool)> {
+            pos.iter()
+                .map(|&pos| (pos, true))
+                .chain([(pos[pos.len() - 1], false)])
+                .collect()
+        };
+        // a brush of a single frame
+        let clicks = clicks_at(&mut button, &tap(&[inside]));
+        assert_eq!(clicks, [false, false]);
+        // held for a few frames, jittering a little
+        let clicks = clicks_at(&mut button, &tap(&[inside, nearby, inside, nearby]));
+        assert_eq!(clicks, [false, false, false, false, true]);
+        // long enough, but slid across the button
+        let clicks = clicks_at(&mut button, &tap(&[inside, nearby, far, far]));
+        assert_eq!(clicks, [false, false, false, false, false]);
+        // touch has its own threshold, which is off here
+        let pointer = |pos, down, just_pressed| Pointer {
+            pos,
+            touch: true,
+            ..pointer(down, just_pressed)
+        };
+        button.update_button_state(&pointer(inside, true, true), 1. / 60., true);
+        let event = button.update_button_state(&pointer(inside, false, false), 1. / 60., true);
+        assert_eq!(event, Some(ButtonEvent::Clicked));
+    }
+
+    #[test]
+    fn button_events_mark_the_edges() {
+        use ButtonEvent::*;
+        let mut button = Button::new_for_test(ButtonType::Sun, Rect::new(0., 0., 100., 100.));
+        let (inside, outside) = (vec2(50., 50.), vec2(150., 50.));
+        let frames = [
+            (outside, false),
+            (inside, false),
+            (inside, false),
+            (inside, true),
+            (outside, true),
+            (inside, true),
+            (inside, false),
+            (inside, false),
+            (outside, false),
+        ];
+        let mut down_before = false;
+        let events: Vec<_> = frames
+            .iter()
+            .map(|&(pos, down)| {
+                let pointer = Pointer {
+                    pos,
+                    ..pointer(down, down && !down_before)
+                };
+                down_before = down;
+                button.update_button_state(&pointer, 1. / 60., true)
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                None,
+                Some(Entered),
+                None,
+                Some(Pressed),
+                None,
+                None,
+                Some(Clicked),
+                None,
+                Some(Left)
+            ]
+        );
+    }
+
+    #[test]
+    fn holding_through_a_transition_doesnt_click_once_enabled_again() {
+        let mut button = Button::new_for_test(ButtonType::Sun, Rect::new(0., 0., 100., 100.));
+        let inside = vec2(50., 50.);
+        let clicks = clicks_while(
+            &mut button,
+            &[
+                (inside, true, true),
+                // something else started a transition, hiding the button while it's held
+                (inside, true, false),
+                (inside, true, false),
+                (inside, true, true),
+                (inside, false, true),
+            ],
+        );
+        assert_eq!(clicks, [false; 5]);
+        let clicks = clicks_at(&mut button, &[(inside, true), (inside, false)]);
+        assert_eq!(clicks, [false, true]);
+    }
+
+    #[test]
+    fn buttons_click_where_they_are() {
+        let mut button = Button::new_for_test(ButtonType::Sun, Rect::new(100., 100., 200., 200.));
+        let (inside, outside) = (vec2(150., 250.), vec2(50., 250.));
+        let clicks = clicks_at(
+            &mut button,
+            &[(inside, false), (inside, true), (inside, false)],
+        );
+        assert_eq!(clicks, [false, false, true]);
+        let clicks = clicks_at(
+            &mut button,
+            &[(outside, false), (outside, true), (inside, false)],
+        );
+        assert_eq!(clicks, [false, false, false]);
+    }
+
+    #[test]
+    fn hovering_buttons_counts_up_until_the_pointer_leaves() {
+        let mut button = Button::new_for_test(ButtonType::Water, Rect::new(0., 0., 100., 100.));
+        let inside = vec2(50., 50.);
+        clicks_at(&mut button, &[(inside, false); 30]);
+        assert!(button.is_hovered());
+        assert!((button.hover_time - 0.5).abs() < 1e-4);
+        clicks_at(&mut button, &[(vec2(150., 50.), false)]);
+        assert!(!button.is_hovered());
+        assert_eq!(button.hover_time, 0.);
+    }
+
+    #[test]
+    fn growing_to_the_tap_size_keeps_the_button_centered() {
+        let mut button = Button::new_for_test(ButtonType::Restart, Rect::new(0., 0., 100., 50.));
+        button.fit_to_tap_size(100., 1.);
+        assert_eq!(button.dest, Rect::new(-50., -25., 200., 100.));
+        button.fit_to_tap_size(10., 1.);
+        assert_eq!(button.dest, Rect::new(0., 0., 100., 50.));
+    }
+
+    #[test]
+    fn the_fixed_timestep_keeps_the_same_pace_at_any_frame_rate() {
+        for hz in [30., 60., 144., 240.] {
+            let mut timestep = FixedTimestep::default();
+            let steps: usize = (0..hz as usize).map(|_| timestep.steps(1. / hz)).sum();
+            // a second's worth, give or take the step that's still left over
+            assert!((119..=120).contains(&steps), "{} steps at {} Hz", steps, hz);
+            assert!(timestep.left_over < FIXED_STEP);
+        }
+    }
+
+    #[test]
+    fn cracks_only_keep_the_world_busy_with_a_flash() {
+        // without --crack-flash there's nothing to wait for, so the world is idle again right away
+        let mut flash = CrackFlash::new(None);
+        flash.start();
+        assert!(flash.is_over());
+        let mut flash = CrackFlash::new(Some(Flash::default()));
+        flash.start();
+        assert!(!flash.is_over());
+        flash.update(Flash::default().duration);
+        assert!(flash.is_over());
+    }
+}
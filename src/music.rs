@@ -0,0 +1,160 @@
+//! Looping background music, one track per kind of creature, crossfaded whenever the kind changes.
+
+use macroquad::audio::{self, PlaySoundParams, Sound};
+
+use crate::{error::GameError, WorldState, ASSET_PATH};
+
+/// how long one track takes to fade out while the next fades in, in seconds
+const CROSSFADE_SECS: f32 = 2.5;
+/// music volume relative to the sound effects
+const MUSIC_VOLUME: f32 = 0.5;
+
+impl WorldState {
+    /// the file in `assets/music/` playing while in this state, every state has one so far, though
+    /// [`Music::play`] would fade out to silence for a None
+    pub fn music_track(&self) -> Option<&'static str> {
+        use WorldState::*;
+        Some(match self {
+            Egg | EggCrack1 | EggCrack2 | BigEgg | BigEggCrack1 | BigEggCrack2 => "eggs.mp3",
+            Chick | Duckling | Duck | Bird | Heron => "birds.mp3",
+            BabyTurtle | Salamander | Dragonmander | Turtle | TurtleWizard => "reptiles.mp3",
+            SmallDragon | Nessi | Kraken | Jellyfish => "deep.mp3",
+        })
+    }
+}
+
+struct Track {
+    name: &'static str,
+    sound: Sound,
+    /// 0 is silent, 1 is full volume
+    fade: f32,
+}
+
+#[derive(Default)]
+pub struct Music {
+    /// every track that could be loaded, missing files are simply left out
+    loaded: Vec<(&'static str, Sound)>,
+    current: Option<Track>,
+    /// the track fading out while `current` fades in
+    previous: Option<Track>,
+    volume: f32,
+    /// silenced by [`Music::pause`], the tracks loop on quietly since macroquad can't pause a sound
+    paused: bool,
+}
+
+impl Music {
+    /// Loads every track any state wants. With `volume` 0 nothing is loaded at all.
+    pub async fn load(volume: f32) -> Music {
+        let mut music = Music {
+            volume: volume * MUSIC_VOLUME,
+            ..Default::default()
+        };
+        if music.volume <= 0. {
+            return music;
+        }
+        for name in WorldState::all().filter_map(|state| state.music_track()) {
+            if music.loaded.iter().any(|(loaded, _)| *loaded == name) {
+                continue;
+            }
+            let path = format!("{}music/{}", ASSET_PATH, name);
+            // music is optional, so a missing file just means silence
+            match audio::load_sound(&path).await {
+                Ok(sound) => music.loaded.push((name, sound)),
+                Err(e) => {
+                    let error = GameError::Audio {
+                        file: path,
+                        message: e.to_string(),
+                    };
+                    log_debug!("no music, {}", error);
+                }
+            }
+        }
+        music
+    }
+
+    /// Stops every track right away, the fading one as well. The next `play` starts from silence.
+    pub fn stop(&mut self) {
+        for track in [self.current.take(), self.previous.take()]
+            .into_iter()
+            .flatten()
+        {
+            audio::stop_sound(&track.sound);
+        }
+    }
+
+    /// Starts crossfading into `track`, unless it's already the one playing. Only the track fading in
+    /// and the one fading out keep looping, any older one is stopped.
+    pub fn play(&mut self, track: Option<&'static str>) {
+        if self.current.as_ref().map(|t| t.name) == track {
+            return;
+        }
+        if let Some(previous) = self.previous.take() {
+            audio::stop_sound(&previous.sound);
+        }
+        self.previous = self.current.take();
+
+        let loaded = self.loaded.iter().find(|(name, _)| Some(*name) == track);
+        if let Some(&(name, ref sound)) = loaded {
+            audio::play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: 0.,
+                },
+            );
+            self.current = Some(Track {
+                name,
+                sound: sound.clone(),
+                fade: 0.,
+            });
+        }
+    }
+
+    /// Drops the music back to silence to fade the current track in again from there, the one fading out
+    /// is stopped. For coming back after the window was away, so that it doesn't blare on where it left off.
+    pub fn fade_in_again(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            audio::stop_sound(&previous.sound);
+        }
+        if let Some(ref mut current) = self.current {
+            current.fade = 0.;
+            audio::set_sound_volume(&current.sound, 0.);
+        }
+    }
+
+    /// Silences every track until [`Music::resume`], which fades the current one back in.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        for track in self.current.iter().chain(self.previous.iter()) {
+            audio::set_sound_volume(&track.sound, 0.);
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.fade_in_again();
+    }
+
+    /// advances the crossfade
+    pub fn update(&mut self, delta_secs: f32) {
+        if self.paused {
+            return;
+        }
+        let step = delta_secs / CROSSFADE_SECS;
+        if let Some(ref mut current) = self.current {
+            if current.fade < 1. {
+                current.fade = (current.fade + step).min(1.);
+                audio::set_sound_volume(&current.sound, current.fade * self.volume);
+            }
+        }
+        if let Some(ref mut previous) = self.previous {
+            previous.fade -= step;
+            if previous.fade <= 0. {
+                audio::stop_sound(&previous.sound);
+                self.previous = None;
+            } else {
+                audio::set_sound_volume(&previous.sound, previous.fade * self.volume);
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! A logo drawn in a corner on top of the world, for branded deployments.
+
+use macroquad::prelude::*;
+
+use crate::{config::LogoConfig, ASSET_PATH};
+
+pub struct Overlay {
+    texture: Texture2D,
+    dest: Rect,
+    opacity: f32,
+}
+
+impl Overlay {
+    /// Loads `assets/logo.png`, None if the logo is turned off or the file is missing.
+    pub async fn load(config: &LogoConfig) -> Option<Overlay> {
+        if !config.shown {
+            return None;
+        }
+        let path = format!("{}logo.png", ASSET_PATH);
+        let texture = match load_texture(&path).await {
+            Ok(texture) => texture,
+            Err(e) => {
+                log_info!("no logo, couldn't load {}: {}", path, e);
+                return None;
+            }
+        };
+        // the height follows from the width, so that the logo keeps its proportions
+        let height = config.width * texture.height() / texture.width();
+        Some(Overlay {
+            texture,
+            dest: Rect::new(config.pos.x, config.pos.y, config.width, height),
+            opacity: config.opacity,
+        })
+    }
+
+    pub fn draw(&self) {
+        draw_texture_ex(
+            &self.texture,
+            self.dest.x,
+            self.dest.y,
+            Color::new(1., 1., 1., self.opacity),
+            DrawTextureParams {
+                dest_size: Some(self.dest.size()),
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Small cosmetic particles drawn on top of the world, like the confetti for a new leaf creature.
+
+use macroquad::{prelude::*, rand::gen_range};
+
+use crate::{config::Emitter, WORLD_HEIGHT, WORLD_WIDTH};
+
+const GRAVITY: f32 = 1800.;
+const CONFETTI_COUNT: usize = 160;
+const CONFETTI_COLORS: [Color; 5] = [GOLD, PINK, SKYBLUE, LIME, ORANGE];
+const SPARKLE_COUNT: usize = 40;
+/// how much an ambient particle's speed and direction vary from the emitter's velocity, relatively
+const AMBIENT_SPREAD: f32 = 0.3;
+
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    color: Color,
+    size: f32,
+    /// seconds left until it disappears
+    life: f32,
+    /// the life it started with, for fading out
+    max_life: f32,
+    /// how much gravity pulls on it, 0 for floating
+    weight: f32,
+    /// part of a state's ambience rather than a one-off burst
+    ambient: bool,
+}
+
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    /// the fraction of an ambient particle that's due but not spawned yet
+    ambient_due: f32,
+}
+
+impl Particles {
+    /// confetti shooting up from the bottom corners of the world
+    pub fn burst_confetti(&mut self) {
+        for i in 0..CONFETTI_COUNT {
+            let from_left = i % 2 == 0;
+            let x = if from_left { 0. } else { WORLD_WIDTH };
+            let angle = gen_range(0.15, 0.45) * std::f32::consts::PI;
+            let speed = gen_range(2200., 3600.);
+            let life = gen_range(1.8, 3.);
+            self.particles.push(Particle {
+                pos: vec2(x, WORLD_HEIGHT),
+                vel: vec2(
+                    if from_left { 1. } else { -1. } * angle.cos() * speed,
+                    -angle.sin() * speed,
+                ),
+                color: CONFETTI_COLORS[i % CONFETTI_COLORS.len()],
+                size: gen_range(30., 60.),
+                life,
+                max_life: life,
+                weight: 1.,
+                ambient: false,
+            });
+        }
+    }
+
+    /// a few golden glints drifting out of `rect`, for a shiny creature appearing in it
+    pub fn burst_sparkles(&mut self, rect: Rect) {
+        for _ in 0..SPARKLE_COUNT {
+            let angle = gen_range(0., std::f32::consts::TAU);
+            let speed = gen_range(40., 160.);
+            let life = gen_range(0.8, 2.);
+            self.particles.push(Particle {
+                pos: vec2(
+                    gen_range(rect.left(), rect.right()),
+                    gen_range(rect.top(), rect.bottom()),
+                ),
+                vel: vec2(angle.cos(), angle.sin()) * speed,
+                color: Color::new(1., 0.95, 0.6, 0.9),
+                size: gen_range(14., 34.),
+                life,
+                max_life: life,
+                weight: 0.,
+                ambient: false,
+            });
+        }
+    }
+
+    /// Spawns however many particles `emitter` has due after another `delta_secs`, anywhere in `rect`,
+    /// drifting along the emitter's velocity.
+    pub fn emit_ambient(&mut self, emitter: &Emitter, rect: Rect, delta_secs: f32) {
+        self.ambient_due += emitter.rate * delta_secs;
+        while self.ambient_due >= 1. {
+            self.ambient_due -= 1.;
+            let turn = gen_range(-AMBIENT_SPREAD, AMBIENT_SPREAD);
+            let speed = gen_range(1. - AMBIENT_SPREAD, 1. + AMBIENT_SPREAD);
+            let life = gen_range(2., 4.);
+            self.particles.push(Particle {
+                pos: vec2(
+                    gen_range(rect.left(), rect.right()),
+                    gen_range(rect.top(), rect.bottom()),
+                ),
+                vel: Vec2::from_angle(turn).rotate(emitter.velocity) * speed,
+                color: emitter.color,
+                size: gen_range(16., 36.),
+                life,
+                max_life: life,
+                weight: 0.,
+                ambient: true,
+            });
+        }
+    }
+
+    /// removes every ambient particle at once, e.g. as the state they belong to is left
+    pub fn clear_ambient(&mut self) {
+        self.particles.retain(|p| !p.ambient);
+        self.ambient_due = 0.;
+    }
+
+    /// whether any burst is still flying, ambient particles don't count since they never stop
+    pub fn is_bursting(&self) -> bool {
+        self.particles.iter().any(|p| !p.ambient)
+    }
+
+    pub fn update(&mut self, delta_secs: f32) {
+        for p in self.particles.iter_mut() {
+            p.vel.y += GRAVITY * p.weight * delta_secs;
+            // a little air resistance, so that the confetti floats down
+            p.vel *= 1. - 1.5 * delta_secs.min(0.5);
+            p.pos += p.vel * delta_secs;
+            p.life -= delta_secs;
+        }
+        self.particles.retain(|p| p.life > 0.);
+    }
+
+    pub fn draw(&self) {
+        for p in self.particles.iter() {
+            let color = Color {
+                a: p.color.a * (p.life / p.max_life).min(1.),
+                ..p.color
+            };
+            draw_rectangle(p.pos.x, p.pos.y, p.size, p.size * 0.6, color);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Everything that is saved goes through here: plain files on native, the browser's localStorage on the web.
+//!
+//! Keys look like relative paths, e.g. `profiles/anna/save.txt`, which is exactly where they end up on native.
+
+use crate::error::GameError;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::path::Path;
+
+    pub fn get(key: &str) -> Option<String> {
+        std::fs::read_to_string(key).ok()
+    }
+
+    pub fn set(key: &str, value: &str) -> Result<(), String> {
+        if let Some(dir) = Path::new(key).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(key, value).map_err(|e| e.to_string())
+    }
+
+    pub fn children(prefix: &str) -> Vec<String> {
+        std::fs::read_dir(prefix)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect()
+    }
+}
+
+/// Talks to `persistence.js`, which has to be loaded next to the miniquad bundle.
+/// Strings coming back from JS are handed over in two steps: the call returns their length in bytes
+/// (or -1 for none) and `persistence_take` then copies them into a buffer of that size.
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    extern "C" {
+        fn persistence_get(key: *const u8, key_len: usize) -> i32;
+        fn persistence_set(key: *const u8, key_len: usize, value: *const u8, value_len: usize);
+        fn persistence_children(prefix: *const u8, prefix_len: usize) -> i32;
+        fn persistence_take(buf: *mut u8);
+    }
+
+    fn take(len: i32) -> Option<String> {
+        let mut buf = vec![0u8; usize::try_from(len).ok()?];
+        unsafe { persistence_take(buf.as_mut_ptr()) };
+        String::from_utf8(buf).ok()
+    }
+
+    pub fn get(key: &str) -> Option<String> {
+        take(unsafe { persistence_get(key.as_ptr(), key.len()) })
+    }
+
+    pub fn set(key: &str, value: &str) -> Result<(), String> {
+        unsafe { persistence_set(key.as_ptr(), key.len(), value.as_ptr(), value.len()) };
+        Ok(())
+    }
+
+    pub fn children(prefix: &str) -> Vec<String> {
+        take(unsafe { persistence_children(prefix.as_ptr(), prefix.len()) })
+            .map(|names| names.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// the value stored under `key`, None if it has never been set
+pub fn get(key: &str) -> Option<String> {
+    backend::get(key)
+}
+
+pub fn set(key: &str, value: &str) -> Result<(), GameError> {
+    backend::set(key, value).map_err(|message| GameError::Save {
+        key: key.to_string(),
+        message,
+    })
+}
+
+/// The names one level below `prefix` that hold keys of their own, e.g. the profiles in `profiles/`.
+pub fn children(prefix: &str) -> Vec<String> {
+    backend::children(prefix)
+}
@@ -0,0 +1,174 @@
+//! Named save profiles, each one holding its own save and stats files under its own keys,
+//! and the screen to pick one at launch.
+
+use macroquad::prelude::*;
+
+use crate::{error::GameError, persistence, WORLD_HEIGHT, WORLD_WIDTH};
+
+const PROFILES_DIR: &str = "profiles/";
+const MAX_NAME_LEN: usize = 16;
+
+#[derive(Clone)]
+pub struct Profile {
+    pub name: String,
+}
+
+impl Profile {
+    /// Only letters, digits, `-` and `_` are allowed, so that the name is safe to use in a key (and as a directory).
+    pub fn new(name: &str) -> Result<Profile, String> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if valid {
+            Ok(Profile {
+                name: name.to_string(),
+            })
+        } else {
+            Err(format!(
+                "profile names need 1 to {} letters, digits, - or _, got {}",
+                MAX_NAME_LEN, name
+            ))
+        }
+    }
+
+    /// names of all profiles that have been saved before, sorted
+    pub fn list() -> Vec<String> {
+        let mut names = persistence::children(PROFILES_DIR);
+        names.sort();
+        names
+    }
+
+    /// the persistence key of one of this profile's files
+    fn key(&self, file: &str) -> String {
+        format!("{}{}/{}", PROFILES_DIR, self.name, file)
+    }
+
+    /// the contents of one of this profile's files, None if it hasn't been written yet
+    pub fn read(&self, file: &str) -> Option<String> {
+        persistence::get(&self.key(file))
+    }
+
+    pub fn write(&self, file: &str, contents: &str) -> Result<(), GameError> {
+        persistence::set(&self.key(file), contents)
+    }
+}
+
+const ROW_HEIGHT: f32 = 260.;
+const FIRST_ROW_Y: f32 = 900.;
+const TEXT_SIZE: f32 = 160.;
+
+/// Lets the player click (or arrow-key to) an existing profile, or type the name of a new one.
+struct ProfileSelection {
+    existing: Vec<String>,
+    /// row under the cursor or chosen with the arrow keys, the last row is the new profile
+    highlighted: usize,
+    new_name: String,
+    error: Option<String>,
+}
+
+impl ProfileSelection {
+    fn new() -> Self {
+        Self {
+            existing: Profile::list(),
+            highlighted: 0,
+            new_name: String::new(),
+            error: None,
+        }
+    }
+
+    fn row_rect(row: usize) -> Rect {
+        Rect::new(
+            200.,
+            FIRST_ROW_Y + row as f32 * ROW_HEIGHT - ROW_HEIGHT * 0.7,
+            WORLD_WIDTH - 400.,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// returns the chosen profile, once there is one
+    fn update(&mut self, cam: &Camera2D) -> Option<Profile> {
+        let rows = self.existing.len() + 1;
+        let new_row = rows - 1;
+
+        // typing always goes into the name of the new profile
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() && self.new_name.len() < MAX_NAME_LEN {
+                self.new_name.push(c);
+                self.highlighted = new_row;
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.new_name.pop();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.highlighted = (self.highlighted + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.highlighted = (self.highlighted + rows - 1) % rows;
+        }
+
+        let mouse_pos = crate::mouse_world_pos(cam);
+        let hovered = (0..rows).find(|&row| Self::row_rect(row).contains(mouse_pos));
+        if let Some(row) = hovered {
+            self.highlighted = row;
+        }
+        let chosen = is_key_pressed(KeyCode::Enter)
+            || (hovered.is_some() && is_mouse_button_pressed(MouseButton::Left));
+        if !chosen {
+            return None;
+        }
+
+        let name = match self.existing.get(self.highlighted) {
+            Some(name) => name.as_str(),
+            None => self.new_name.as_str(),
+        };
+        match Profile::new(name) {
+            Ok(profile) => Some(profile),
+            Err(msg) => {
+                self.error = Some(msg);
+                None
+            }
+        }
+    }
+
+    fn draw(&self) {
+        draw_text("Who's playing?", 200., 500., 220., WHITE);
+        let new_row = format!("new: {}_", self.new_name);
+        let rows = self
+            .existing
+            .iter()
+            .map(|name| name.as_str())
+            .chain(std::iter::once(new_row.as_str()));
+        for (row, text) in rows.enumerate() {
+            let color = if row == self.highlighted {
+                WHITE
+            } else {
+                Color::new(0.6, 0.6, 0.6, 1.)
+            };
+            let rect = Self::row_rect(row);
+            draw_text(text, rect.x, rect.y + ROW_HEIGHT * 0.7, TEXT_SIZE, color);
+        }
+        if let Some(ref error) = self.error {
+            draw_text(error, 200., WORLD_HEIGHT - 300., 70., RED);
+        }
+    }
+}
+
+/// Shows the profile selection until a profile is chosen, None if the window is closed instead.
+pub async fn select_profile(cam: &Camera2D) -> Option<Profile> {
+    let mut selection = ProfileSelection::new();
+    loop {
+        if crate::quit_requested() {
+            return None;
+        }
+        clear_background(Color::default());
+        set_camera(cam);
+        if let Some(profile) = selection.update(cam) {
+            return Some(profile);
+        }
+        selection.draw();
+        next_frame().await;
+    }
+}
@@ -0,0 +1,33 @@
+//! A small seedable random number generator, so that a simulation can be replayed exactly.
+
+/// xorshift64*, plenty for picking sounds and the like
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // the state must never be 0, so mix the seed up a bit first
+        Self {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
+    }
+
+    /// uniformly in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// true with probability 1/2
+    pub fn coin_flip(&mut self) -> bool {
+        self.next_u32() & 1 == 0
+    }
+}
@@ -0,0 +1,150 @@
+//! Saving and loading the world per profile: the current state and which states have been discovered.
+//!
+//! Every file is plain text made of `key=value` lines.
+
+use crate::{
+    profile::Profile,
+    stats::{Stats, STATS_FILE},
+    World, WorldState, WORLD_STATE_VARIANTS,
+};
+
+const SAVE_FILE: &str = "save.txt";
+
+/// the `key=value` pairs in `text`, ignoring anything else
+pub fn entries(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// the states a profile has seen at least once, and when it first did
+#[derive(Clone, Default)]
+pub struct Discovered {
+    /// in seconds since the Unix epoch, 0 for states discovered before the time was saved too
+    states: [Option<u64>; WORLD_STATE_VARIANTS],
+    /// which states have been seen shiny, tracked apart from the others
+    shiny: [bool; WORLD_STATE_VARIANTS],
+}
+
+impl Discovered {
+    /// remembers `state` as discovered right now, unless it was already
+    pub fn insert(&mut self, state: WorldState) {
+        let now = macroquad::miniquad::date::now() as u64;
+        self.states[state as usize].get_or_insert(now);
+    }
+
+    pub fn insert_shiny(&mut self, state: WorldState) {
+        self.insert(state);
+        self.shiny[state as usize] = true;
+    }
+
+    pub fn contains_shiny(&self, state: WorldState) -> bool {
+        self.shiny[state as usize]
+    }
+
+    pub fn contains(&self, state: WorldState) -> bool {
+        self.states[state as usize].is_some()
+    }
+
+    /// when `state` was discovered, in seconds since the Unix epoch, if it was and that's known
+    pub fn first_discovered(&self, state: WorldState) -> Option<u64> {
+        self.states[state as usize].filter(|&time| time > 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = WorldState> + '_ {
+        WorldState::ALL
+            .into_iter()
+            .filter(|&state| self.contains(state))
+    }
+
+    /// `Name@time` for every state, separated by commas
+    fn to_text(&self) -> String {
+        self.iter()
+            .map(|state| format!("{:?}@{}", state, self.states[state as usize].unwrap_or(0)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// the names of the states seen shiny, separated by commas
+    fn shiny_to_text(&self) -> String {
+        WorldState::ALL
+            .into_iter()
+            .filter(|&state| self.contains_shiny(state))
+            .map(|state| format!("{:?}", state))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn read_shiny(&mut self, text: &str) {
+        for state in text.split(',').filter_map(WorldState::from_name) {
+            self.shiny[state as usize] = true;
+        }
+    }
+
+    /// older saves have only the names, without the time
+    fn from_text(text: &str) -> Discovered {
+        let mut discovered = Discovered::default();
+        for entry in text.split(',') {
+            let (name, time) = entry.split_once('@').unwrap_or((entry, ""));
+            if let Some(state) = WorldState::from_name(name) {
+                discovered.states[state as usize] = Some(time.parse().unwrap_or(0));
+            }
+        }
+        discovered
+    }
+}
+
+impl World {
+    /// Writes the current state, the discovered set and the stats into the active profile, if there is one.
+    pub fn save(&self) {
+        let Some(ref profile) = self.profile else {
+            return;
+        };
+        let save = format!(
+            "state={:?}\ndiscovered={}\nshiny={}\n",
+            self.sim.state,
+            self.discovered.to_text(),
+            self.discovered.shiny_to_text()
+        );
+        let result = profile
+            .write(SAVE_FILE, &save)
+            .and_then(|_| profile.write(STATS_FILE, &self.stats.to_text()));
+        if let Err(e) = result {
+            log_error!("{}", e);
+        }
+    }
+
+    /// Saves the active profile and continues with `profile` from wherever it was left,
+    /// or from the egg if it's new.
+    pub fn switch_profile(&mut self, profile: Profile) {
+        self.save();
+
+        let mut state = WorldState::Egg;
+        let mut discovered = Discovered::default();
+        let mut shiny = "";
+        let text = profile.read(SAVE_FILE).unwrap_or_default();
+        for (key, value) in entries(&text) {
+            match key {
+                "state" => state = WorldState::from_name(value).unwrap_or(state),
+                "discovered" => discovered = Discovered::from_text(value),
+                "shiny" => shiny = value,
+                _ => {}
+            }
+        }
+        discovered.read_shiny(shiny);
+        // saves only happen at rest, so a crack would mean the file is broken
+        if self.sim.graph.is_crack(state) {
+            state = WorldState::Egg;
+        }
+        discovered.insert(state);
+
+        self.stats = profile
+            .read(STATS_FILE)
+            .map(|text| Stats::from_text(&text))
+            .unwrap_or_default();
+        self.discovered = discovered;
+        self.sim.jump_to(state);
+        self.music.play(state.music_track());
+        self.profile = Some(profile);
+    }
+}
@@ -0,0 +1,169 @@
+//! Scripted sequences of clicks and waits, so that demos and test scenarios can be written down and
+//! played back the same way every time, with `--script=<file>`.
+//!
+//! A script has one command per line, blank lines and everything after a `#` are ignored:
+//!
+//! ```text
+//! wait 2       # seconds
+//! click sun    # or water, arrow(head), restart
+//! restart      # the same as click restart
+//! ```
+
+use crate::{error::GameError, ButtonType, World};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Click(ButtonType),
+    /// let this many seconds pass
+    Wait(f32),
+}
+
+/// The steps in `text`, or an error naming the first line that isn't a valid command.
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let step = match words[..] {
+            [] => continue,
+            ["wait", secs] => match secs.parse::<f32>() {
+                Ok(secs) if secs >= 0. => Step::Wait(secs),
+                _ => return Err(format!("line {}: expected seconds, got {}", i + 1, secs)),
+            },
+            ["click", button] => match ButtonType::from_name(button) {
+                Some(b_type) => Step::Click(b_type),
+                None => return Err(format!("line {}: unknown button {}", i + 1, button)),
+            },
+            ["restart"] => Step::Click(ButtonType::Restart),
+            _ => return Err(format!("line {}: unknown command {}", i + 1, line.trim())),
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+/// Plays a script back along the real frame time.
+pub struct Player {
+    steps: Vec<Step>,
+    /// the index of the step to do next
+    next: usize,
+    /// how much of the current wait has passed already
+    waited: f32,
+}
+
+impl Player {
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self {
+            steps,
+            next: 0,
+            waited: 0.,
+        }
+    }
+
+    /// every click that's due after another `delta_secs` have passed, in order
+    pub fn update(&mut self, mut delta_secs: f32) -> Vec<ButtonType> {
+        let mut clicks = Vec::new();
+        while let Some(&step) = self.steps.get(self.next) {
+            match step {
+                Step::Click(b_type) => clicks.push(b_type),
+                Step::Wait(secs) => {
+                    let left = secs - self.waited;
+                    if delta_secs < left {
+                        self.waited += delta_secs;
+                        break;
+                    }
+                    delta_secs -= left;
+                    self.waited = 0.;
+                }
+            }
+            self.next += 1;
+        }
+        clicks
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.steps.len()
+    }
+}
+
+/// The script at `path`, ready to play, or an error naming the file and the broken line.
+pub async fn load(path: &str) -> Result<Player, GameError> {
+    let error = |message| GameError::Asset {
+        path: path.to_string(),
+        message,
+    };
+    let text = macroquad::file::load_string(path)
+        .await
+        .map_err(|e| error(e.to_string()))?;
+    let steps = parse(&text).map_err(error)?;
+    Ok(Player::new(steps))
+}
+
+impl World {
+    /// Clicks whatever the script has due in this frame, the same way as a player clicking the button would.
+    pub fn run_script(&mut self, delta_secs: f32) {
+        let Some(ref mut script) = self.script else {
+            return;
+        };
+        let clicks = script.update(delta_secs);
+        let finished = script.finished();
+        for b_type in clicks {
+            if self.sim.click(b_type) {
+                self.on_trigger();
+            } else {
+                log_warn!("script: {:?} did nothing in {:?}", b_type, self.sim.state);
+            }
+        }
+        if finished {
+            log_info!("script finished");
+            self.script = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_with_comments_and_blank_lines() {
+        use ButtonType::*;
+        let steps = parse("wait 2\n\nclick sun # hatch\n  wait 0.5\nrestart\n").unwrap();
+        assert_eq!(
+            steps,
+            [
+                Step::Wait(2.),
+                Step::Click(Sun),
+                Step::Wait(0.5),
+                Step::Click(Restart)
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            parse("wait 1\nclick moon\n"),
+            Err("line 2: unknown button moon".to_string())
+        );
+        assert_eq!(
+            parse("# intro\njump\n"),
+            Err("line 2: unknown command jump".to_string())
+        );
+        assert_eq!(
+            parse("wait soon"),
+            Err("line 1: expected seconds, got soon".to_string())
+        );
+    }
+
+    #[test]
+    fn clicks_come_due_after_their_waits() {
+        let mut player = Player::new(parse("click sun\nwait 1\nclick water\nwait 1").unwrap());
+        assert_eq!(player.update(0.), [ButtonType::Sun]);
+        assert_eq!(player.update(0.6), []);
+        assert_eq!(player.update(0.6), [ButtonType::Water]);
+        assert!(!player.finished());
+        assert_eq!(player.update(5.), []);
+        assert!(player.finished());
+    }
+}
@@ -0,0 +1,91 @@
+//! A log of one play session for exhibitions, written as JSON when the game is quit, with `--log-sessions`.
+//!
+//! Sessions go through [`crate::persistence`] under `sessions/<start timestamp>.json`,
+//! so into files on native and into the browser's localStorage on the web.
+
+use macroquad::time::get_time;
+
+use crate::{graph::StateGraph, persistence, WorldState};
+
+const SESSIONS_DIR: &str = "sessions/";
+
+struct SessionEvent {
+    /// seconds since the session started
+    t: f64,
+    kind: &'static str,
+    from: WorldState,
+    to: WorldState,
+}
+
+pub struct SessionLog {
+    /// unix time in seconds, which also names the file
+    started_at: f64,
+    /// `get_time` at the start, which the event times are relative to
+    start_time: f64,
+    events: Vec<SessionEvent>,
+    /// the furthest state from the eggs that was reached, with how far it is
+    deepest: Option<(WorldState, usize)>,
+}
+
+impl SessionLog {
+    pub fn start() -> SessionLog {
+        SessionLog {
+            started_at: macroquad::miniquad::date::now(),
+            start_time: get_time(),
+            events: Vec::new(),
+            deepest: None,
+        }
+    }
+
+    /// a transition from `from` completed into `to`
+    pub fn record_reached(&mut self, from: WorldState, to: WorldState, graph: &StateGraph) {
+        let kind = if graph.is_crack(to) {
+            "crack"
+        } else {
+            "reached"
+        };
+        self.events.push(SessionEvent {
+            t: get_time() - self.start_time,
+            kind,
+            from,
+            to,
+        });
+        if let Some(depth) = graph.depth(to) {
+            if self.deepest.is_none_or(|(_, deepest)| depth > deepest) {
+                self.deepest = Some((to, depth));
+            }
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let events: Vec<String> = self
+            .events
+            .iter()
+            .map(|e| {
+                format!(
+                    "    {{\"t\": {:.3}, \"type\": \"{}\", \"from\": \"{:?}\", \"to\": \"{:?}\"}}",
+                    e.t, e.kind, e.from, e.to
+                )
+            })
+            .collect();
+        let deepest = match self.deepest {
+            Some((state, _)) => format!("\"{:?}\"", state),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\n  \"started\": {:.0},\n  \"duration\": {:.3},\n  \"deepest\": {},\n  \"events\": [\n{}\n  ]\n}}\n",
+            self.started_at,
+            get_time() - self.start_time,
+            deepest,
+            events.join(",\n")
+        )
+    }
+
+    pub fn write(&self) {
+        let key = format!("{}{:.0}.json", SESSIONS_DIR, self.started_at);
+        match persistence::set(&key, &self.to_json()) {
+            Ok(()) => log_info!("wrote the session log to {}", key),
+            Err(e) => log_error!("{}", e),
+        }
+    }
+}
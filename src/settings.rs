@@ -0,0 +1,190 @@
+//! The settings menu behind F1, for rebinding keys and the preferences that aren't about keys.
+
+use macroquad::prelude::*;
+
+use crate::{
+    bindings::{Action, KeyBindings},
+    persistence,
+    save::entries,
+    WORLD_HEIGHT, WORLD_WIDTH,
+};
+
+/// not inside a profile, just like the key bindings
+const PREFERENCES_KEY: &str = "preferences.txt";
+
+/// everything in the settings menu besides the keys, saved across launches
+#[derive(Clone, Default)]
+pub struct Preferences {
+    /// cuts between states with a quick fade instead of the long crossfade, leaves out the confetti
+    /// and switches the button colors right away
+    pub reduced_motion: bool,
+}
+
+impl Preferences {
+    /// the saved preferences, with the defaults for anything missing
+    pub fn load() -> Preferences {
+        let mut preferences = Preferences::default();
+        let text = persistence::get(PREFERENCES_KEY).unwrap_or_default();
+        for (key, value) in entries(&text) {
+            if key == "reduced-motion" {
+                preferences.reduced_motion = value == "true";
+            }
+        }
+        preferences
+    }
+
+    pub fn save(&self) {
+        let text = format!("reduced-motion={}\n", self.reduced_motion);
+        if let Err(e) = persistence::set(PREFERENCES_KEY, &text) {
+            log_error!("{}", e);
+        }
+    }
+}
+
+/// the rows after the one per action
+const REDUCED_MOTION_ROW: usize = Action::ALL.len();
+const RESTORE_ROW: usize = Action::ALL.len() + 1;
+
+const ROW_HEIGHT: f32 = 220.;
+const FIRST_ROW_Y: f32 = 900.;
+const TEXT_SIZE: f32 = 120.;
+
+struct Settings {
+    bindings: KeyBindings,
+    preferences: Preferences,
+    /// row under the cursor or chosen with the arrow keys, the last row restores the default keys
+    highlighted: usize,
+    /// waiting for the key to bind to the highlighted action
+    capturing: bool,
+    message: Option<String>,
+}
+
+impl Settings {
+    fn rows() -> usize {
+        RESTORE_ROW + 1
+    }
+
+    fn row_rect(row: usize) -> Rect {
+        Rect::new(
+            200.,
+            FIRST_ROW_Y + row as f32 * ROW_HEIGHT - ROW_HEIGHT * 0.7,
+            WORLD_WIDTH - 400.,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// returns whether the menu should close
+    fn update(&mut self, cam: &Camera2D) -> bool {
+        if self.capturing {
+            if let Some(key) = get_last_key_pressed() {
+                let action = Action::ALL[self.highlighted];
+                self.message = self.bindings.rebind(action, key).err();
+                self.capturing = false;
+            }
+            return false;
+        }
+        if is_key_pressed(KeyCode::F1) {
+            return true;
+        }
+
+        let rows = Self::rows();
+        if is_key_pressed(KeyCode::Down) {
+            self.highlighted = (self.highlighted + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.highlighted = (self.highlighted + rows - 1) % rows;
+        }
+        let mouse_pos = crate::mouse_world_pos(cam);
+        let hovered = (0..rows).find(|&row| Self::row_rect(row).contains(mouse_pos));
+        if let Some(row) = hovered {
+            self.highlighted = row;
+        }
+        let chosen = is_key_pressed(KeyCode::Enter)
+            || (hovered.is_some() && is_mouse_button_pressed(MouseButton::Left));
+        if chosen {
+            self.message = None;
+            match self.highlighted {
+                REDUCED_MOTION_ROW => {
+                    self.preferences.reduced_motion = !self.preferences.reduced_motion
+                }
+                RESTORE_ROW => self.bindings = KeyBindings::default(),
+                _ => self.capturing = true,
+            }
+        }
+        false
+    }
+
+    fn draw(&self) {
+        draw_text("Settings", 200., 500., 220., WHITE);
+        for (row, action) in Action::ALL.into_iter().enumerate() {
+            let keys = if self.capturing && row == self.highlighted {
+                "press a key...".to_string()
+            } else {
+                let names: Vec<String> = self
+                    .bindings
+                    .keys(action)
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect();
+                names.join(", ")
+            };
+            self.draw_row(row, &format!("{}: {}", action.display_name(), keys));
+        }
+        let reduced_motion = if self.preferences.reduced_motion {
+            "on"
+        } else {
+            "off"
+        };
+        self.draw_row(
+            REDUCED_MOTION_ROW,
+            &format!("Reduced motion: {}", reduced_motion),
+        );
+        self.draw_row(RESTORE_ROW, "Restore default keys");
+        if let Some(ref message) = self.message {
+            draw_text(message, 200., WORLD_HEIGHT - 400., 70., RED);
+        }
+        draw_text("F1 to close", 200., WORLD_HEIGHT - 250., 70., GRAY);
+    }
+
+    fn draw_row(&self, row: usize, text: &str) {
+        let color = if row == self.highlighted {
+            WHITE
+        } else {
+            Color::new(0.6, 0.6, 0.6, 1.)
+        };
+        let rect = Self::row_rect(row);
+        draw_text(text, rect.x, rect.y + ROW_HEIGHT * 0.7, TEXT_SIZE, color);
+    }
+}
+
+/// Shows the settings menu until it's closed again, then saves and returns the new bindings and preferences.
+/// If the window is closed instead, they're returned as they are, unsaved.
+pub async fn settings_menu(
+    cam: &Camera2D,
+    bindings: KeyBindings,
+    preferences: Preferences,
+) -> (KeyBindings, Preferences) {
+    let mut settings = Settings {
+        bindings,
+        preferences,
+        highlighted: 0,
+        capturing: false,
+        message: None,
+    };
+    // the F1 that opened the menu is still pressed in this frame
+    next_frame().await;
+    loop {
+        if crate::quit_requested() {
+            return (settings.bindings, settings.preferences);
+        }
+        clear_background(Color::default());
+        set_camera(cam);
+        if settings.update(cam) {
+            settings.bindings.save();
+            settings.preferences.save();
+            return (settings.bindings, settings.preferences);
+        }
+        settings.draw();
+        next_frame().await;
+    }
+}
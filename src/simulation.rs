@@ -0,0 +1,912 @@
+//! The state machine of the world on its own, without textures, sounds or input,
+//! so that it can be stepped (and tested) headless.
+
+use std::collections::VecDeque;
+
+use smallvec::SmallVec;
+
+use crate::{
+    config::TransitionConfig, graph::StateGraph, rng::Rng, ButtonType, SoundIndex, Transition,
+    TransitionType, WorldState,
+};
+
+/// what happened during one call to [`Simulation::progress`], for the world to react to
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    /// a transition's sound is due
+    Sound(SoundIndex),
+    /// a transition completed into this state, the intro doesn't count
+    Reached(WorldState),
+    /// the world came to rest after reaching a state, with no transition following
+    Rest,
+}
+
+pub type Events = SmallVec<[Event; 4]>;
+/// see [`Simulation::current_options`]
+pub type Options = SmallVec<[(ButtonType, WorldState); 4]>;
+
+/// where the buttons lead
+#[derive(Clone, Copy)]
+pub enum NavigationMode {
+    /// along the edges of the state graph
+    Normal,
+    /// for demos: each button straight to a chosen creature (indexed by `ButtonType`), from anywhere,
+    /// egg cracks are ignored, since those need the graph to continue
+    Sandbox([Option<WorldState>; 4]),
+}
+
+pub struct Simulation {
+    pub state: WorldState,
+    pub transition: Option<Transition>,
+    pub graph: StateGraph,
+    /// which buttons can be clicked, indexed by `ButtonType`
+    enabled: [bool; 4],
+    /// whether a regular transition can be redirected by another button, see [`Simulation::click`]
+    pub interruptible: bool,
+    pub navigation: NavigationMode,
+    /// the button that led to the current state, None at the start or after going back
+    pub origin: Option<ButtonType>,
+    /// the button that cracked the egg the current path started from, None after jumping anywhere
+    pub first_button: Option<ButtonType>,
+    /// Restart walks back to the egg one state at a time instead of going there directly
+    pub unwind: bool,
+    /// the chance for the creatures hatching from an egg to be shiny, 0 to never roll for it
+    pub shiny_chance: f32,
+    /// whether the creatures of the current line are shiny, rolled once as their egg starts cracking
+    pub shiny: bool,
+    /// every state the world came to rest in since it was last put somewhere, oldest first, for unwinding
+    history: Vec<WorldState>,
+    /// with more than one, regular transitions play this many scale notes up and back down in turn
+    /// instead of a random one of the first two, see [`SoundIndex::SCALE`]
+    pub melody_notes: usize,
+    /// how many notes into the melody it is
+    melody_step: usize,
+    /// the states still to go to one after another, once the running transition is done
+    queue: VecDeque<WorldState>,
+    transitions: TransitionConfig,
+    rng: Rng,
+    /// its own stream for the shiny rolls, so that rolling them leaves the random sounds as they'd be without
+    shiny_rng: Rng,
+}
+
+impl Simulation {
+    pub fn new(transitions: TransitionConfig, seed: u64) -> Self {
+        let mut sim = Self {
+            state: WorldState::Egg,
+            transition: None,
+            graph: StateGraph::default(),
+            enabled: [false; 4],
+            interruptible: false,
+            navigation: NavigationMode::Normal,
+            origin: None,
+            first_button: None,
+            unwind: false,
+            shiny_chance: 0.,
+            shiny: false,
+            melody_notes: 0,
+            melody_step: 0,
+            history: vec![WorldState::Egg],
+            queue: VecDeque::new(),
+            transitions,
+            rng: Rng::new(seed),
+            shiny_rng: Rng::new(!seed),
+        };
+        sim.sync_enabled_to_state();
+        sim
+    }
+
+    pub fn is_enabled(&self, b_type: ButtonType) -> bool {
+        self.enabled[b_type as usize]
+    }
+
+    /// where `b_type` leads from the current state, depending on the navigation mode
+    pub fn goal_for(&self, b_type: ButtonType) -> Option<WorldState> {
+        match self.navigation {
+            NavigationMode::Normal => self.graph.next(self.state, b_type),
+            NavigationMode::Sandbox(goals) => {
+                goals[b_type as usize].filter(|&goal| !self.graph.is_crack(goal))
+            }
+        }
+    }
+
+    /// enables exactly the buttons that lead somewhere from the current state
+    pub fn sync_enabled_to_state(&mut self) {
+        for b_type in ButtonType::ALL {
+            self.enabled[b_type as usize] = self.goal_for(b_type).is_some();
+        }
+    }
+
+    /// Every button that can be clicked right now, with the state it leads to in the end (past any egg cracks).
+    /// Empty while a transition runs, since clicks are ignored then.
+    pub fn current_options(&self) -> Options {
+        if self.transition.is_some() {
+            return Options::new();
+        }
+        ButtonType::ALL
+            .into_iter()
+            .filter(|&b_type| self.is_enabled(b_type))
+            .filter_map(|b_type| {
+                let goal = match self.navigation {
+                    NavigationMode::Normal => self.graph.peek(self.state, b_type),
+                    NavigationMode::Sandbox(_) => self.goal_for(b_type),
+                };
+                Some((b_type, goal?))
+            })
+            .collect()
+    }
+
+    /// Puts the world straight into `state`, without any transition.
+    pub fn jump_to(&mut self, state: WorldState) {
+        self.state = state;
+        self.origin = None;
+        self.first_button = None;
+        self.shiny = false;
+        self.history = vec![state];
+        self.queue.clear();
+        self.transition = None;
+        self.sync_enabled_to_state();
+    }
+
+    /// whether the buttons should be visible: at rest, and during regular transitions (morphs included)
+    /// if they can be interrupted
+    pub fn buttons_shown(&self) -> bool {
+        match self.transition {
+            None => true,
+            Some(ref t) => {
+                self.interruptible
+                    && matches!(
+                        t.t_type,
+                        TransitionType::Regular(_)
+                            | TransitionType::Return(_)
+                            | TransitionType::Morph(_)
+                    )
+            }
+        }
+    }
+
+    /// Swaps in `graph` for the current one, if it passes `StateGraph::validate`. Only at rest,
+    /// since a running transition may need edges the new graph doesn't have anymore.
+    #[cfg(any(test, feature = "hot-reload"))]
+    pub fn replace_graph(&mut self, graph: StateGraph) -> Result<(), crate::error::GameError> {
+        if self.transition.is_some() {
+            return Err(crate::error::GameError::Graph(
+                "can't swap the graph during a transition".to_string(),
+            ));
+        }
+        graph.validate()?;
+        self.graph = graph;
+        self.queue.clear();
+        self.sync_enabled_to_state();
+        Ok(())
+    }
+
+    /// The player clicked `b_type`. Returns whether that started a transition, which it doesn't
+    /// for disabled buttons or while a transition is running (the buttons are hidden then anyway),
+    /// unless it's interruptible and can be redirected.
+    pub fn click(&mut self, b_type: ButtonType) -> bool {
+        if !self.is_enabled(b_type) {
+            return false;
+        }
+        if self.transition.is_some() {
+            return self.buttons_shown() && self.redirect(b_type);
+        }
+        self.enabled[b_type as usize] = false;
+        self.start_transition(b_type);
+        true
+    }
+
+    /// Clicks only the first of `clicks`, all registered in the same frame, so that the button deciding
+    /// where an egg crack leads is never ambiguous. Returns whether that started a transition.
+    pub fn click_first(&mut self, clicks: &[ButtonType]) -> bool {
+        match clicks.first() {
+            Some(&b_type) => self.click(b_type),
+            None => false,
+        }
+    }
+
+    /// Retargets the running regular (or morphing) transition to where `b_type` leads from the current state,
+    /// crossfading from the frame that's visible right now. Egg cracks can't be redirected to.
+    fn redirect(&mut self, b_type: ButtonType) -> bool {
+        let Some(goal_state) = self.goal_for(b_type) else {
+            return false;
+        };
+        if self.graph.is_crack(goal_state) {
+            return false;
+        }
+        let Some(old) = self.transition.take() else {
+            return false;
+        };
+        self.queue.clear();
+        let mut t = Transition::new(
+            goal_state,
+            TransitionType::Regular(Some(b_type)),
+            &self.transitions,
+        );
+        let progress = old.time_progressed / old.total_duration();
+        // the crossfade is symmetric: in the first half the old image is still fading out, so carry on with it,
+        // in the second half the old goal is fading in, so fade it out again from the same alpha instead
+        let (from_image, relative_progress) = if progress <= 0.5 {
+            (old.from_image, progress)
+        } else {
+            (Some(old.goal_state), 1. - progress)
+        };
+        t.from_image = from_image;
+        t.time_progressed = relative_progress * t.total_duration();
+        self.enabled[b_type as usize] = false;
+        self.transition = Some(t);
+        true
+    }
+
+    /// Goes back to where the current state came from, skipping over egg cracks, if that's unambiguous
+    /// (see [`StateGraph::parent`]). Returns whether that started a transition.
+    pub fn back(&mut self) -> bool {
+        if self.transition.is_some() {
+            return false;
+        }
+        let Some(mut parent) = self.graph.parent(self.state) else {
+            return false;
+        };
+        while self.graph.is_crack(parent) {
+            let Some(p) = self.graph.parent(parent) else {
+                return false;
+            };
+            parent = p;
+        }
+        self.transition = Some(Transition::new(
+            parent,
+            TransitionType::Regular(None),
+            &self.transitions,
+        ));
+        true
+    }
+
+    /// Starts the transition `b_type` leads to from the current state, regardless of which buttons are enabled.
+    pub fn start_transition(&mut self, b_type: ButtonType) {
+        // compute the target
+        let goal_state = self
+            .goal_for(b_type)
+            .unwrap_or_else(|| panic!("{:?} not available in {:?}!", b_type, self.state));
+        if self.unwind && b_type == ButtonType::Restart && self.start_unwinding(goal_state) {
+            return;
+        }
+        // start the new transition
+        let t_type = if self.graph.is_crack(goal_state) {
+            self.first_button = Some(b_type);
+            // the cracks after the first carry on by themselves, but the roll is once per egg either way
+            if !self.graph.is_crack(self.state) {
+                self.shiny =
+                    self.shiny_chance > 0. && self.shiny_rng.next_f32() < self.shiny_chance;
+            }
+            TransitionType::EggCracking(b_type)
+        } else if b_type == ButtonType::Restart && self.graph.is_leaf(self.state) {
+            TransitionType::Return(self.state)
+        } else if self.transitions.morphs(self.state, goal_state) {
+            TransitionType::Morph(b_type)
+        } else {
+            TransitionType::Regular(Some(b_type))
+        };
+        log_info!(
+            "transition from {:?} to {:?} via {:?}",
+            self.state,
+            goal_state,
+            b_type
+        );
+        self.transition = Some(Transition::new(goal_state, t_type, &self.transitions));
+    }
+
+    /// Queues a transition to every state on the way back to `egg`: the ones rested in on the way here,
+    /// if the history goes back that far, or else those on the shortest way through the graph.
+    /// Egg cracks are left out, like with [`Simulation::back`]. Returns false if there's no way back.
+    fn start_unwinding(&mut self, egg: WorldState) -> bool {
+        let way_here = match self.history.iter().position(|&state| state == egg) {
+            Some(start) => self.history[start..].to_vec(),
+            None => match self.graph.shortest_path(egg, self.state) {
+                Some(path) => path,
+                None => return false,
+            },
+        };
+        self.queue = way_here
+            .into_iter()
+            .rev()
+            .skip(1)
+            .filter(|&state| !self.graph.is_crack(state))
+            .collect();
+        log_info!("unwinding from {:?} via {:?}", self.state, self.queue);
+        match self.next_queued() {
+            Some(t) => {
+                self.transition = Some(t);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the states still to go through after the running transition, in order
+    pub fn queued(&self) -> impl Iterator<Item = WorldState> + '_ {
+        self.queue.iter().copied()
+    }
+
+    /// the transition to the next queued state, if there is one
+    fn next_queued(&mut self) -> Option<Transition> {
+        let goal_state = self.queue.pop_front()?;
+        Some(Transition::new(
+            goal_state,
+            TransitionType::Regular(None),
+            &self.transitions,
+        ))
+    }
+
+    /// Fades the current state in from black.
+    pub fn start_intro(&mut self) {
+        self.transition = Some(Transition::new(
+            self.state,
+            TransitionType::Intro,
+            &self.transitions,
+        ));
+    }
+
+    /// Progresses the running transition, and everything it continues into for as long as `delta_secs` lasts,
+    /// so that even a huge delta goes through every transition in between, in order.
+    pub fn progress(&mut self, delta_secs: f32) -> Events {
+        let mut events = Events::new();
+        let mut remaining = delta_secs;
+        while let Some(mut t) = self.transition.take() {
+            remaining = t.progress(remaining);
+            if let Some(sound_index) = t.sound_to_play(&mut self.rng, &self.graph) {
+                let sound_index = match sound_index {
+                    SoundIndex::Scale1 | SoundIndex::Scale2 if self.melody_notes > 1 => {
+                        self.next_note()
+                    }
+                    _ => sound_index,
+                };
+                events.push(Event::Sound(sound_index));
+            }
+            if !t.completed() {
+                // its slightly weird to but back the transition, but who knows, maybe the compiler is smart enough to make this free, maybe not
+                self.transition = Some(t);
+                break;
+            }
+            let next_transition = t.subsequent_transition(&self.transitions, &self.graph);
+            self.finish_transition(&t, next_transition, &mut events);
+        }
+        events
+    }
+
+    /// Completes the running transition right away, along with any egg cracks it continues into,
+    /// without any of the sounds still due.
+    pub fn skip_transition(&mut self) -> Events {
+        let mut events = Events::new();
+        while let Some(mut t) = self.transition.take() {
+            t.sound_fired = true;
+            let remaining = t.total_duration() - t.time_progressed;
+            self.transition = Some(t);
+            events.extend(self.progress(remaining));
+        }
+        events.retain(|event| !matches!(event, Event::Sound(_)));
+        events
+    }
+
+    /// Some transitions require a final action, such as the restart or enabling the restart button
+    fn finish_transition(
+        &mut self,
+        t: &Transition,
+        next_transition: Option<Transition>,
+        events: &mut Events,
+    ) {
+        // the intro only shows the state the world is in already
+        if let TransitionType::Intro = t.t_type {
+            self.transition = next_transition;
+            return;
+        }
+        self.state = t.goal_state;
+        log_info!("reached {:?}", t.goal_state);
+        events.push(Event::Reached(t.goal_state));
+
+        let next_transition = next_transition.or_else(|| self.next_queued());
+        if next_transition.is_none() {
+            // the buttons that lead somewhere are exactly those that haven't been used up on the way here
+            self.sync_enabled_to_state();
+            self.origin = t.t_type.trigger();
+            self.record_rest();
+            events.push(Event::Rest);
+        }
+        self.transition = next_transition;
+    }
+
+    /// the next note of the melody, going up the scale and back down again without repeating the ends
+    fn next_note(&mut self) -> SoundIndex {
+        let period = 2 * (self.melody_notes - 1);
+        let i = self.melody_step % period;
+        self.melody_step += 1;
+        SoundIndex::SCALE[if i < self.melody_notes { i } else { period - i }]
+    }
+
+    /// adds the state just rested in to the history, or cuts it back to where it was last, after going back
+    fn record_rest(&mut self) {
+        match self.history.iter().position(|&state| state == self.state) {
+            Some(i) => self.history.truncate(i + 1),
+            None => self.history.push(self.state),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.state,
+            transition: self
+                .transition
+                .as_ref()
+                .map(|t| (t.goal_state, t.time_progressed)),
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// What a simulation looks like at one point in time, comparable across runs.
+#[cfg(test)]
+pub struct Snapshot {
+    pub state: WorldState,
+    /// goal and seconds progressed of the running transition
+    pub transition: Option<(WorldState, f32)>,
+    pub enabled: [bool; 4],
+}
+
+#[cfg(test)]
+impl std::fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.state)?;
+        if let Some((goal, time)) = self.transition {
+            write!(f, " -> {:?} @{:.3}", goal, time)?;
+        }
+        let enabled: Vec<String> = ButtonType::ALL
+            .into_iter()
+            .filter(|&b| self.enabled[b as usize])
+            .map(|b| format!("{:?}", b))
+            .collect();
+        write!(f, " [{}]", enabled.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WORLD_STATE_VARIANTS;
+
+    fn options_in(state: WorldState) -> String {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.jump_to(state);
+        format!("{:?}", sim.current_options())
+    }
+
+    #[test]
+    fn options_lead_past_the_egg_cracks() {
+        assert_eq!(
+            options_in(WorldState::Egg),
+            "[(Sun, Chick), (Water, BabyTurtle), (Arrowhead, BigEgg)]"
+        );
+        assert_eq!(
+            options_in(WorldState::Chick),
+            "[(Water, Duckling), (Arrowhead, Bird)]"
+        );
+        assert_eq!(options_in(WorldState::Duck), "[(Restart, Egg)]");
+    }
+
+    #[test]
+    fn back_skips_the_egg_cracks() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.jump_to(WorldState::Chick);
+        assert!(sim.back());
+        sim.skip_transition();
+        assert_eq!(sim.state, WorldState::Egg);
+        assert!(!sim.back());
+    }
+
+    #[test]
+    fn back_from_the_big_egg_leads_to_the_egg() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.jump_to(WorldState::BigEgg);
+        assert!(sim.back());
+        sim.skip_transition();
+        assert_eq!(sim.state, WorldState::Egg);
+    }
+
+    #[test]
+    fn morphing_edges_morph_hatching_included() {
+        use WorldState::*;
+        let config = TransitionConfig {
+            morphs: vec![(EggCrack2, Chick), (Chick, Bird)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config, 0);
+        assert!(sim.click(ButtonType::Sun));
+        while sim.state != EggCrack2 {
+            sim.progress(0.5);
+        }
+        let t_type = sim.transition.as_ref().unwrap().t_type;
+        assert!(matches!(t_type, TransitionType::Morph(ButtonType::Sun)));
+        sim.skip_transition();
+        assert!(sim.click(ButtonType::Arrowhead));
+        let t_type = sim.transition.as_ref().unwrap().t_type;
+        assert!(matches!(
+            t_type,
+            TransitionType::Morph(ButtonType::Arrowhead)
+        ));
+        sim.skip_transition();
+        assert!(sim.click(ButtonType::Water));
+        let t_type = sim.transition.as_ref().unwrap().t_type;
+        assert!(matches!(t_type, TransitionType::Regular(_)));
+    }
+
+    #[test]
+    fn morphs_take_their_goals_duration_and_can_be_interrupted() {
+        use WorldState::*;
+        let mut config = TransitionConfig {
+            morphs: vec![(Chick, Bird)],
+            ..Default::default()
+        };
+        config.durations[Bird as usize] = Some(7.);
+        let mut sim = Simulation::new(config, 0);
+        sim.interruptible = true;
+        sim.jump_to(Chick);
+        assert!(sim.click(ButtonType::Arrowhead));
+        assert_eq!(sim.transition.as_ref().unwrap().total_duration(), 7.);
+        assert!(sim.buttons_shown());
+        assert!(sim.click(ButtonType::Water));
+        assert_eq!(sim.transition.as_ref().unwrap().goal_state, Duckling);
+    }
+
+    #[test]
+    fn interruptible_transitions_redirect_from_the_visible_frame() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.interruptible = true;
+        sim.jump_to(WorldState::Chick);
+        assert!(sim.click(ButtonType::Water));
+        sim.progress(7.);
+        assert!(sim.click(ButtonType::Arrowhead));
+        let t = sim.transition.as_ref().unwrap();
+        assert_eq!(t.goal_state, WorldState::Bird);
+        assert_eq!(t.from_image, Some(WorldState::Duckling));
+        assert!((t.time_progressed - (9.3 - 7.)).abs() < 1e-4);
+        sim.skip_transition();
+        assert_eq!(sim.state, WorldState::Bird);
+    }
+
+    #[test]
+    fn sandbox_goals_ignore_the_graph() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        let mut goals = [None; 4];
+        goals[ButtonType::Sun as usize] = Some(WorldState::Jellyfish);
+        sim.navigation = NavigationMode::Sandbox(goals);
+        sim.sync_enabled_to_state();
+        assert_eq!(format!("{:?}", sim.current_options()), "[(Sun, Jellyfish)]");
+        assert!(sim.click(ButtonType::Sun));
+        sim.skip_transition();
+        assert_eq!(sim.state, WorldState::Jellyfish);
+        assert!(sim.is_enabled(ButtonType::Sun));
+        assert!(!sim.is_enabled(ButtonType::Restart));
+    }
+
+    /// the states reached on the way, and where it ended up
+    fn reached_by_restart(sim: &mut Simulation) -> Vec<WorldState> {
+        assert!(sim.click(ButtonType::Restart));
+        let mut reached = Vec::new();
+        while sim.transition.is_some() {
+            for event in sim.progress(1.) {
+                if let Event::Reached(state) = event {
+                    reached.push(state);
+                }
+            }
+        }
+        reached
+    }
+
+    #[test]
+    fn unwinding_retraces_the_way_here() {
+        use WorldState::*;
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.unwind = true;
+        for b_type in [ButtonType::Sun, ButtonType::Arrowhead, ButtonType::Water] {
+            assert!(sim.click(b_type));
+            sim.skip_transition();
+        }
+        assert_eq!(sim.state, Heron);
+        assert_eq!(reached_by_restart(&mut sim), [Bird, Chick, Egg]);
+        assert!(sim.is_enabled(ButtonType::Sun));
+    }
+
+    #[test]
+    fn unwinding_without_history_takes_the_shortest_way() {
+        use WorldState::*;
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.unwind = true;
+        sim.jump_to(Nessi);
+        assert_eq!(reached_by_restart(&mut sim), [SmallDragon, BigEgg, Egg]);
+    }
+
+    #[test]
+    fn restart_goes_straight_to_the_egg_without_unwinding() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.jump_to(WorldState::Nessi);
+        assert_eq!(reached_by_restart(&mut sim), [WorldState::Egg]);
+    }
+
+    #[test]
+    fn restart_is_all_that_leaf_states_offer() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        for state in WorldState::ALL {
+            sim.jump_to(state);
+            let only_restart = format!("{:?}", sim.current_options()) == "[(Restart, Egg)]";
+            assert_eq!(only_restart, sim.graph.is_leaf(state), "{:?}", state);
+            if sim.graph.is_leaf(state) {
+                for b_type in ButtonType::ALL {
+                    assert_eq!(sim.is_enabled(b_type), b_type == ButtonType::Restart);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_egg_cracking_button_is_remembered_until_the_leaf() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        for b_type in [ButtonType::Water, ButtonType::Sun, ButtonType::Arrowhead] {
+            assert!(sim.click(b_type));
+            sim.skip_transition();
+        }
+        assert!(sim.graph.is_leaf(sim.state));
+        assert_eq!(sim.first_button, Some(ButtonType::Water));
+        sim.jump_to(WorldState::Egg);
+        assert_eq!(sim.first_button, None);
+    }
+
+    #[test]
+    fn the_first_click_in_a_frame_decides_the_hatchling() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        assert!(sim.click_first(&[ButtonType::Sun, ButtonType::Water]));
+        sim.skip_transition();
+        assert_eq!(sim.state, WorldState::Chick);
+        assert_eq!(sim.origin, Some(ButtonType::Sun));
+    }
+
+    #[test]
+    fn no_options_during_a_transition() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        assert!(sim.click(ButtonType::Sun));
+        assert!(sim.current_options().is_empty());
+    }
+
+    /// Clicks random buttons (enabled or not), goes back, skips and lets random time pass,
+    /// checking after every step that the simulation is somewhere it's allowed to be, and in the end
+    /// that it's been everywhere.
+    fn soak(sim: &mut Simulation, seed: u64, steps: usize) {
+        let mut rng = Rng::new(seed);
+        let mut visited = [false; WORLD_STATE_VARIANTS];
+        for step in 0..steps {
+            match rng.next_u32() % 10 {
+                0..=4 => {
+                    let b_type = ButtonType::ALL[rng.next_u32() as usize % ButtonType::ALL.len()];
+                    sim.click(b_type);
+                }
+                5 => {
+                    sim.back();
+                }
+                6 => {
+                    sim.skip_transition();
+                }
+                _ => {
+                    let delta = (rng.next_u32() % 1000) as f32 / 1000.;
+                    sim.progress(delta);
+                }
+            }
+
+            assert!(WorldState::ALL.contains(&sim.state), "step {}", step);
+            visited[sim.state as usize] = true;
+            match sim.transition {
+                None => {
+                    assert!(
+                        !sim.graph.is_crack(sim.state),
+                        "resting in {:?} at step {}",
+                        sim.state,
+                        step
+                    );
+                    let enabled = ButtonType::ALL
+                        .into_iter()
+                        .filter(|&b| sim.is_enabled(b))
+                        .count();
+                    assert_eq!(sim.current_options().len(), enabled, "step {}", step);
+                    assert!(enabled > 0, "dead end {:?} at step {}", sim.state, step);
+                }
+                Some(ref t) => {
+                    assert!(t.time_progressed <= t.total_duration(), "step {}", step);
+                    if let TransitionType::EggCracking(_) = t.t_type {
+                        assert!(sim.graph.is_crack(t.goal_state), "step {}", step);
+                    }
+                }
+            }
+        }
+        let unvisited: Vec<WorldState> = WorldState::ALL
+            .into_iter()
+            .filter(|&state| !visited[state as usize])
+            .collect();
+        assert!(unvisited.is_empty(), "never visited {:?}", unvisited);
+    }
+
+    #[test]
+    fn random_play_never_gets_stuck() {
+        soak(
+            &mut Simulation::new(TransitionConfig::default(), 7),
+            7,
+            50_000,
+        );
+    }
+
+    #[test]
+    fn random_interrupted_play_never_gets_stuck() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 11);
+        sim.interruptible = true;
+        soak(&mut sim, 11, 50_000);
+    }
+
+    #[test]
+    fn melodies_go_up_and_down_the_scale() {
+        use SoundIndex::*;
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.melody_notes = 3;
+        sim.jump_to(WorldState::Chick);
+        let mut notes = Vec::new();
+        for b_type in [
+            ButtonType::Arrowhead,
+            ButtonType::Water,
+            ButtonType::Restart,
+            ButtonType::Sun,
+            ButtonType::Arrowhead,
+        ] {
+            assert!(sim.click(b_type));
+            for event in sim.progress(60.) {
+                if let Event::Sound(sound) = event {
+                    notes.push(sound);
+                }
+            }
+        }
+        assert!(
+            matches!(
+                notes[..],
+                [Scale1, Scale2, Scale3, Crack1, Crack2, Scale2, Scale1]
+            ),
+            "{:?}",
+            notes
+        );
+    }
+
+    #[test]
+    fn one_huge_delta_goes_through_the_whole_hatching_in_order() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        assert!(sim.click(ButtonType::Sun));
+        let events = sim.progress(60.);
+        assert_eq!(sim.state, WorldState::Chick);
+        assert!(sim.transition.is_none());
+        assert!(
+            matches!(
+                events[..],
+                [
+                    Event::Sound(SoundIndex::Crack1),
+                    Event::Reached(WorldState::EggCrack1),
+                    Event::Sound(SoundIndex::Crack2),
+                    Event::Reached(WorldState::EggCrack2),
+                    Event::Sound(SoundIndex::Scale1 | SoundIndex::Scale2),
+                    Event::Reached(WorldState::Chick),
+                    Event::Rest,
+                ]
+            ),
+            "{:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn zero_durations_still_reach_the_goal_with_every_sound() {
+        let mut transitions = TransitionConfig::default();
+        transitions.regular.duration = 0.;
+        transitions.egg_cracking.duration = 0.;
+        let mut sim = Simulation::new(transitions, 0);
+        assert!(sim.click(ButtonType::Sun));
+        let mut sounds = Vec::new();
+        for _ in 0..10 {
+            for event in sim.progress(1. / 60.) {
+                if let Event::Sound(sound) = event {
+                    sounds.push(sound);
+                }
+            }
+        }
+        assert_eq!(sim.state, WorldState::Chick);
+        assert!(sim.transition.is_none());
+        assert!(matches!(
+            sounds[..],
+            [
+                SoundIndex::Crack1,
+                SoundIndex::Crack2,
+                SoundIndex::Scale1 | SoundIndex::Scale2
+            ]
+        ));
+    }
+
+    #[test]
+    fn a_replaced_graph_takes_effect_at_once_unless_it_has_orphans() {
+        let text = std::fs::read_to_string("assets/states.txt").unwrap();
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.jump_to(WorldState::Chick);
+        let orphaning = text.replace("chick water duckling", "");
+        let error = sim
+            .replace_graph(StateGraph::parse(&orphaning).unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Duckling"), "{}", error);
+        assert!(sim.is_enabled(ButtonType::Water));
+
+        let moved = text.replace("chick water duckling", "chick sun duckling");
+        sim.replace_graph(StateGraph::parse(&moved).unwrap())
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", sim.current_options()),
+            "[(Sun, Duckling), (Arrowhead, Bird)]"
+        );
+        assert!(!sim.is_enabled(ButtonType::Water));
+    }
+
+    #[test]
+    fn a_replaced_graph_that_would_get_stuck_is_rejected() {
+        let text = std::fs::read_to_string("assets/states.txt").unwrap();
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        let crack_cut = text.replace("eggcrack1 water eggcrack2", "");
+        assert!(sim
+            .replace_graph(StateGraph::parse(&crack_cut).unwrap())
+            .is_err());
+        let dead_end = text.replace("heron restart egg", "");
+        assert!(sim
+            .replace_graph(StateGraph::parse(&dead_end).unwrap())
+            .is_err());
+        assert_eq!(
+            sim.graph.next(WorldState::Heron, ButtonType::Restart),
+            Some(WorldState::Egg)
+        );
+    }
+
+    #[test]
+    fn the_shiny_roll_happens_as_the_egg_cracks_and_lasts_the_line() {
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.shiny_chance = 1.;
+        assert!(sim.click(ButtonType::Sun));
+        assert!(sim.shiny);
+        sim.progress(100.);
+        assert!(sim.click(ButtonType::Water));
+        sim.progress(100.);
+        assert_eq!(sim.state, WorldState::Duckling);
+        assert!(sim.shiny);
+        sim.jump_to(WorldState::Egg);
+        assert!(!sim.shiny);
+
+        // without a chance there's no roll
+        let mut sim = Simulation::new(TransitionConfig::default(), 0);
+        sim.click(ButtonType::Sun);
+        assert!(!sim.shiny);
+    }
+
+    #[test]
+    fn shiny_rolls_leave_the_random_sounds_as_they_were() {
+        let sounds_with = |shiny_chance: f32| {
+            let mut sim = Simulation::new(TransitionConfig::default(), 7);
+            sim.shiny_chance = shiny_chance;
+            let mut sounds = Vec::new();
+            for _ in 0..8 {
+                assert!(sim.click(ButtonType::Sun));
+                for event in sim.progress(100.) {
+                    if let Event::Sound(sound) = event {
+                        sounds.push(sound);
+                    }
+                }
+                sim.jump_to(WorldState::Egg);
+            }
+            format!("{:?}", sounds)
+        };
+        assert_eq!(sounds_with(0.), sounds_with(0.5));
+    }
+}
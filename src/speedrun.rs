@@ -0,0 +1,105 @@
+//! Racing from the egg to a chosen creature with `--speedrun=<creature>`, with the best time per
+//! creature kept across launches.
+
+use macroquad::prelude::*;
+
+use crate::{persistence, save::entries, WorldState, WORLD_WIDTH};
+
+/// not inside a profile, so that everyone on the machine races against the same times
+const BEST_TIMES_KEY: &str = "speedruns.txt";
+
+pub struct Speedrun {
+    pub target: WorldState,
+    /// the time of the current attempt, None until it starts with the first click from the egg
+    elapsed: Option<f32>,
+    /// the time of the attempt that reached the target last, shown until the next one starts
+    finished: Option<f32>,
+    best: Option<f32>,
+}
+
+impl Speedrun {
+    pub fn new(target: WorldState) -> Self {
+        let text = persistence::get(BEST_TIMES_KEY).unwrap_or_default();
+        let best = entries(&text)
+            .find(|&(name, _)| WorldState::from_name(name) == Some(target))
+            .and_then(|(_, secs)| secs.parse().ok());
+        Self {
+            target,
+            elapsed: None,
+            finished: None,
+            best,
+        }
+    }
+
+    /// The player started a transition from `from`, which starts the clock if that's the egg.
+    pub fn on_trigger(&mut self, from: WorldState) {
+        if self.elapsed.is_none() && from == WorldState::Egg {
+            self.elapsed = Some(0.);
+            self.finished = None;
+        }
+    }
+
+    pub fn update(&mut self, delta_secs: f32) {
+        if let Some(ref mut elapsed) = self.elapsed {
+            *elapsed += delta_secs;
+        }
+    }
+
+    /// Stops the clock on reaching the target, and keeps the time if it's a new best.
+    /// Restarting before that gives up on the attempt.
+    pub fn on_reached(&mut self, state: WorldState) {
+        let Some(elapsed) = self.elapsed else {
+            return;
+        };
+        if state == self.target {
+            self.elapsed = None;
+            self.finished = Some(elapsed);
+            log_info!("speedrun to {:?} in {:.2} s", self.target, elapsed);
+            if self.best.is_none_or(|best| elapsed < best) {
+                self.best = Some(elapsed);
+                self.save_best();
+            }
+        } else if state == WorldState::Egg {
+            self.elapsed = None;
+        }
+    }
+
+    fn save_best(&self) {
+        // the other targets' times are kept as they are
+        let mut text = persistence::get(BEST_TIMES_KEY).unwrap_or_default();
+        text = entries(&text)
+            .filter(|&(name, _)| WorldState::from_name(name) != Some(self.target))
+            .map(|(name, secs)| format!("{}={}\n", name, secs))
+            .collect();
+        if let Some(best) = self.best {
+            text += &format!("{:?}={}\n", self.target, best);
+        }
+        if let Err(e) = persistence::set(BEST_TIMES_KEY, &text) {
+            log_error!("{}", e);
+        }
+    }
+
+    /// the target, the running (or last) time and the best one, across the top
+    pub fn draw(&self, font: Option<&Font>) {
+        let time = self.elapsed.or(self.finished).unwrap_or(0.);
+        let mut text = format!("{}: {:.2}", self.target.display_name(), time);
+        if let Some(best) = self.best {
+            text += &format!("  (best {:.2})", best);
+        }
+        let size = 120.;
+        let dims = measure_text(&text, font, size as u16, 1.);
+        let color = if self.finished.is_some() && self.finished == self.best {
+            GOLD
+        } else {
+            WHITE
+        };
+        crate::strings::draw_text_in(
+            font,
+            &text,
+            (WORLD_WIDTH - dims.width) / 2.,
+            120. + dims.offset_y,
+            size,
+            color,
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Counters about what a profile has been up to, kept across sessions.
+
+use crate::{save, ButtonType, WorldState, WORLD_STATE_VARIANTS};
+
+pub const STATS_FILE: &str = "stats.txt";
+
+#[derive(Clone, Default)]
+pub struct Stats {
+    /// how many transitions were completed, egg cracks included
+    pub transitions: u32,
+    /// how often each state has been reached, indexed by `WorldState`
+    pub reached: [u32; WORLD_STATE_VARIANTS],
+    /// how often each leaf creature was reached after cracking its egg with each button,
+    /// indexed by `WorldState` and then `ButtonType`
+    pub by_first_button: [[u32; 4]; WORLD_STATE_VARIANTS],
+}
+
+impl Stats {
+    pub fn record_reached(&mut self, state: WorldState) {
+        self.transitions += 1;
+        self.reached[state as usize] += 1;
+    }
+
+    pub fn record_leaf(&mut self, state: WorldState, first_button: ButtonType) {
+        self.by_first_button[state as usize][first_button as usize] += 1;
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("transitions={}\n", self.transitions);
+        for state in WorldState::ALL {
+            text += &format!("reached.{:?}={}\n", state, self.reached[state as usize]);
+        }
+        for state in WorldState::ALL {
+            for button in ButtonType::ALL {
+                let count = self.by_first_button[state as usize][button as usize];
+                if count > 0 {
+                    text += &format!("leaf.{:?}.{:?}={}\n", state, button, count);
+                }
+            }
+        }
+        text
+    }
+
+    /// unknown or broken lines are skipped, so an old stats file never loses the rest
+    pub fn from_text(text: &str) -> Stats {
+        let mut stats = Stats::default();
+        for (key, value) in save::entries(text) {
+            let Ok(value) = value.parse() else {
+                continue;
+            };
+            if key == "transitions" {
+                stats.transitions = value;
+            } else if let Some(state) = key.strip_prefix("reached.").and_then(WorldState::from_name)
+            {
+                stats.reached[state as usize] = value;
+            } else if let Some((state, button)) = key
+                .strip_prefix("leaf.")
+                .and_then(|rest| rest.split_once('.'))
+                .and_then(|(state, button)| {
+                    Some((
+                        WorldState::from_name(state)?,
+                        ButtonType::from_name(button)?,
+                    ))
+                })
+            {
+                stats.by_first_button[state as usize][button as usize] = value;
+            }
+        }
+        stats
+    }
+}
@@ -0,0 +1,116 @@
+//! Translatable texts looked up by key, loaded from `assets/lang/<language>.txt` made of `key=value` lines,
+//! and the font they're drawn in.
+
+use macroquad::prelude::*;
+
+use crate::{save::entries, ASSET_PATH};
+
+const LANGUAGE: &str = "en";
+const FONT_PATH: &str = "fonts/main.ttf";
+
+/// The font for the loading screen and the labels, None if it's missing, which means macroquad's default.
+pub async fn load_font() -> Option<Font> {
+    let path = format!("{}{}", ASSET_PATH, FONT_PATH);
+    match load_ttf_font(&path).await {
+        Ok(font) => Some(font),
+        Err(e) => {
+            log_warn!("keeping the default font, couldn't load {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// `draw_text` in `font`, the default one if that's None
+pub fn draw_text_in(font: Option<&Font>, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font,
+            font_size: font_size as u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+#[derive(Default)]
+pub struct Strings {
+    texts: Vec<(String, String)>,
+}
+
+impl Strings {
+    /// Without the file every lookup simply comes up empty.
+    pub async fn load() -> Strings {
+        let path = format!("{}lang/{}.txt", ASSET_PATH, LANGUAGE);
+        let text = match load_string(&path).await {
+            Ok(text) => text,
+            Err(e) => {
+                log_warn!("no texts, couldn't load {}: {}", path, e);
+                return Strings::default();
+            }
+        };
+        Strings {
+            texts: entries(&text)
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.texts
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// Breaks `text` into lines no wider than `max_width` at `font_size`, at spaces only,
+/// so a single word longer than that gets a line of its own.
+pub fn wrap(text: &str, font: Option<&Font>, font_size: u16, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if !line.is_empty() && measure_text(&candidate, font, font_size, 1.).width > max_width {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// `text` wrapped into a dark panel `width` wide, with its top left corner at `x`, `y`
+pub fn draw_text_panel(
+    text: &str,
+    font: Option<&Font>,
+    x: f32,
+    y: f32,
+    width: f32,
+    font_size: u16,
+) {
+    let padding = 60.;
+    let line_height = font_size as f32 * 1.25;
+    let lines = wrap(text, font, font_size, width - 2. * padding);
+    let height = lines.len() as f32 * line_height + 2. * padding;
+    draw_rectangle(x, y, width, height, Color::new(0., 0., 0., 0.75));
+    for (i, line) in lines.iter().enumerate() {
+        draw_text_in(
+            font,
+            line,
+            x + padding,
+            y + padding + line_height * (i as f32 + 0.8),
+            font_size as f32,
+            WHITE,
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! The state textures for memory-constrained devices, with `--texture-cache=<capacity>`: every PNG is
+//! kept as it is in memory, and only the states shown most recently are decoded onto the GPU.
+
+use std::cell::RefCell;
+
+use macroquad::prelude::*;
+
+use crate::{required, WorldState, ASSET_PATH, WORLD_STATE_VARIANTS};
+
+pub struct TextureCache {
+    /// the encoded PNG of every state, indexed by `WorldState`
+    files: Vec<Vec<u8>>,
+    /// the decoded textures, the one used least recently first
+    resident: RefCell<Vec<(WorldState, Texture2D)>>,
+    capacity: usize,
+    filter: FilterMode,
+}
+
+impl TextureCache {
+    /// Reads every state's PNG, decoding none of them yet. A crossfade shows two states at once,
+    /// so `capacity` has to be at least 2 to not decode every frame.
+    pub async fn load(capacity: usize, filter: FilterMode) -> Self {
+        let mut files = Vec::with_capacity(WORLD_STATE_VARIANTS);
+        for i in 0..WORLD_STATE_VARIANTS {
+            let path = format!("{}{}.png", ASSET_PATH, i);
+            files.push(required(load_file(&path).await, &path));
+        }
+        let encoded: usize = files.iter().map(Vec::len).sum();
+        log_info!(
+            "holding the state PNGs in memory ({:.1} MB), at most {} decoded on the GPU",
+            encoded as f32 / 1e6,
+            capacity
+        );
+        Self {
+            files,
+            resident: RefCell::new(Vec::with_capacity(capacity)),
+            capacity,
+            filter,
+        }
+    }
+
+    /// The texture of `state`, decoded if it isn't already, which evicts the one used least recently
+    /// if the cache is full. Only a texture nothing else still holds on to is actually freed.
+    pub fn get(&self, state: WorldState) -> Texture2D {
+        let mut resident = self.resident.borrow_mut();
+        if let Some(i) = resident.iter().position(|&(s, _)| s == state) {
+            let entry = resident.remove(i);
+            let texture = entry.1.clone();
+            resident.push(entry);
+            return texture;
+        }
+        if resident.len() >= self.capacity {
+            let (evicted, _) = resident.remove(0);
+            log_debug!("evicted the texture of {:?}", evicted);
+        }
+        let texture =
+            Texture2D::from_file_with_format(&self.files[state as usize], Some(ImageFormat::Png));
+        texture.set_filter(self.filter);
+        log_debug!("decoded the texture of {:?}", state);
+        resident.push((state, texture.clone()));
+        texture
+    }
+
+    /// The first of `states` that fit into `budget_bytes` of GPU memory together, and the cache, for decoding
+    /// ahead of them being shown, and how many bytes they take. Decoding them with [`Self::get`] from the last
+    /// to the first leaves the first one used most recently.
+    pub fn plan_warm(
+        &self,
+        states: &[WorldState],
+        budget_bytes: usize,
+    ) -> (Vec<WorldState>, usize) {
+        let mut bytes = 0;
+        let mut chosen = Vec::with_capacity(self.capacity);
+        for &state in states.iter().take(self.capacity) {
+            let size = self.decoded_bytes(state);
+            if bytes + size > budget_bytes {
+                break;
+            }
+            bytes += size;
+            chosen.push(state);
+        }
+        (chosen, bytes)
+    }
+
+    /// The texture of `state` for holding on to outside of the cache, e.g. for a whole screen of states
+    /// that wouldn't fit into it. It's decoded if it isn't already, without evicting anything or
+    /// changing which is used least recently.
+    pub fn decode(&self, state: WorldState) -> Texture2D {
+        let resident = self.resident.borrow();
+        if let Some((_, texture)) = resident.iter().find(|&&(s, _)| s == state) {
+            return texture.clone();
+        }
+        let texture =
+            Texture2D::from_file_with_format(&self.files[state as usize], Some(ImageFormat::Png));
+        texture.set_filter(self.filter);
+        texture
+    }
+
+    /// the width and height of the texture of `state` in pixels, going by its PNG header, so without
+    /// decoding it or touching the cache
+    pub fn size(&self, state: WorldState) -> Vec2 {
+        let (width, height) = self.dimensions(state);
+        vec2(width as f32, height as f32)
+    }
+
+    fn dimensions(&self, state: WorldState) -> (usize, usize) {
+        let file = &self.files[state as usize];
+        let dimension = |at: usize| {
+            file.get(at..at + 4)
+                .map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        };
+        // the IHDR chunk right after the 8 byte signature starts with the width and height
+        (dimension(16), dimension(20))
+    }
+
+    /// how many bytes of GPU memory the texture of `state` takes once decoded, going by its size
+    fn decoded_bytes(&self, state: WorldState) -> usize {
+        let (width, height) = self.dimensions(state);
+        width * height * 4
+    }
+
+    /// how many textures are decoded, and roughly how many bytes of GPU memory they take,
+    /// next to how many all of them would take, going by the size of the decoded ones
+    pub fn gpu_usage(&self) -> (usize, usize, usize) {
+        let resident = self.resident.borrow();
+        let bytes: usize = resident
+            .iter()
+            .map(|(_, texture)| crate::texture_bytes(texture))
+            .sum();
+        let all = bytes / resident.len().max(1) * WORLD_STATE_VARIANTS;
+        (resident.len(), bytes, all)
+    }
+}
@@ -0,0 +1,419 @@
+//! A transition between two states, how far it is, how the two states are faded into each other
+//! and when its sound plays. Nothing in here touches the window, so it runs headless like the
+//! [`crate::simulation`] built on it.
+
+use std::f32::consts::PI;
+
+use macroquad::color::Color;
+
+use crate::{
+    config::{ReturnSound, ReturnTransition, TransitionConfig, TransitionTiming},
+    graph::StateGraph,
+    rng::Rng,
+    ButtonType, SoundIndex, WorldState,
+};
+
+/// how much of a regular transition one of its two fades takes
+const CROSSFADE_FADE: f32 = 5. / 14.;
+/// how long one of the two fades takes in seconds with reduced motion, making the crossfade almost a cut
+const REDUCED_MOTION_FADE: f32 = 0.15;
+
+/// how much of a morph the two fades take, both at once
+const MORPH_FADE: f32 = 0.6;
+/// how much bigger the outgoing creature grows in a morph, and how much smaller the incoming one starts
+const MORPH_GROWTH: f32 = 0.25;
+/// how far the outgoing creature turns in a morph, and how far back the incoming one starts, in radians
+const MORPH_TWIST: f32 = 0.35;
+
+/// The alphas of the current and next state at `relative_progress` through a regular transition.
+/// The current one fades out over `fade_length` (relative to the transition) and then the next one fades in
+/// just as long, around the midpoint. `overlap` moves the fades into each other: at 0 one ends where the other
+/// starts, at 1 they happen at the same time.
+fn crossfade_alphas(relative_progress: f32, overlap: f32, fade_length: f32) -> (f32, f32) {
+    let shift = overlap * fade_length / 2.;
+    // 0 before the fade starts, 1 once it's done
+    let fade = |start: f32| ((relative_progress - start) / fade_length).clamp(0., 1.);
+    let out = fade(0.5 + shift - fade_length);
+    let fade_in = fade(0.5 - shift);
+    (
+        ((out * PI).cos() + 1.) / 2.,
+        (1. - (fade_in * PI).cos()) / 2.,
+    )
+}
+
+/// used to differentiate the kinds of transitions existing, but also the two sounds in the game
+#[derive(Clone, Copy, Debug)]
+pub enum TransitionType {
+    /// remembers the button that started it, if any (going back has none)
+    Regular(Option<ButtonType>),
+    EggCracking(ButtonType),
+    /// from the leaf back to its egg, which can be set up per leaf, see [`ReturnTransition`]
+    Return(WorldState),
+    /// the silent fade in from black at the very start
+    Intro,
+    /// a crossfade that warps the two creatures into each other, set up per edge with `--morph`
+    Morph(ButtonType),
+}
+
+/// How a state is scaled and turned around its center while it's drawn during a transition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Warp {
+    pub scale: f32,
+    /// in radians, clockwise
+    pub rotation: f32,
+}
+
+impl Warp {
+    pub const IDENTITY: Warp = Warp {
+        scale: 1.,
+        rotation: 0.,
+    };
+}
+
+impl TransitionType {
+    /// the button the player chose to get here
+    pub fn trigger(&self) -> Option<ButtonType> {
+        match *self {
+            TransitionType::Regular(trigger) => trigger,
+            TransitionType::EggCracking(b_type) => Some(b_type),
+            TransitionType::Return(_) => Some(ButtonType::Restart),
+            TransitionType::Intro => None,
+            TransitionType::Morph(b_type) => Some(b_type),
+        }
+    }
+}
+
+pub struct Transition {
+    pub goal_state: WorldState,
+    pub t_type: TransitionType,
+    /// duration and sound trigger point, looked up from the config once at the start
+    timing: TransitionTiming,
+    pub time_progressed: f32,
+    /// see [`crossfade_alphas`]
+    overlap: f32,
+    /// how a return to the egg looks and sounds, the default for every other transition
+    style: ReturnTransition,
+    /// what fades out in the first half, if not the current state, i.e. the goal of a redirected transition
+    pub from_image: Option<WorldState>,
+    /// true only in the frame in which the sound should be played
+    sound_trigger: bool,
+    /// one-shot guard, so that the sound can't be triggered twice in edge cases
+    pub sound_fired: bool,
+}
+
+impl Transition {
+    pub fn new(goal_state: WorldState, t_type: TransitionType, config: &TransitionConfig) -> Self {
+        Self {
+            goal_state,
+            t_type,
+            timing: config.timing(t_type, goal_state),
+            time_progressed: 0.,
+            overlap: config.crossfade_overlap,
+            style: config.return_style(t_type),
+            from_image: None,
+            sound_trigger: false,
+            sound_fired: false,
+        }
+    }
+
+    /// Progresses the transition and returns how much of `delta_time` was left over after completing it,
+    /// for whatever comes next, 0 if it isn't completed yet.
+    pub fn progress(&mut self, delta_time: f32) -> f32 {
+        self.time_progressed += delta_time;
+        // check for sound to play
+        self.update_sound_to_play();
+
+        if self.completed() {
+            let total = self.total_duration();
+            let leftover_delta = self.time_progressed - total;
+            self.time_progressed = total;
+            return leftover_delta;
+        }
+        0.
+    }
+
+    pub fn total_duration(&self) -> f32 {
+        self.timing.duration
+    }
+
+    /// how far through the transition it is `ahead` seconds from now, from 0 to 1
+    fn relative_progress(&self, ahead: f32) -> f32 {
+        ((self.time_progressed + ahead) / self.total_duration()).clamp(0., 1.)
+    }
+
+    /// The colors of the current and next state `ahead` seconds further on than the transition has been
+    /// stepped to, see [`FixedTimestep`]. `reduced_motion` cuts between them with a quick fade instead.
+    pub fn colors(&self, ahead: f32, reduced_motion: bool) -> (Color, Color) {
+        let color_current_alpha;
+        let color_next_alpha;
+        match self.t_type {
+            TransitionType::Regular(_) | TransitionType::Return(_) => {
+                let relative_progress = self.relative_progress(ahead);
+                let fade_length = if reduced_motion {
+                    (REDUCED_MOTION_FADE / self.total_duration()).min(CROSSFADE_FADE)
+                } else {
+                    CROSSFADE_FADE
+                };
+                let (overlap, fade_length) = if self.style.black > 0. {
+                    // a negative overlap pulls the fades apart, leaving the gap between them black,
+                    // and they get shorter if they don't fit around it
+                    let gap = (self.style.black / self.total_duration()).min(1.);
+                    let fade_length = fade_length.min((1. - gap) / 2.).max(f32::EPSILON);
+                    (-gap / fade_length, fade_length)
+                } else {
+                    (self.overlap, fade_length)
+                };
+                (color_current_alpha, color_next_alpha) =
+                    crossfade_alphas(relative_progress, overlap, fade_length);
+            }
+            TransitionType::EggCracking(_) => {
+                color_current_alpha = 1.;
+                color_next_alpha = 0.;
+            }
+            TransitionType::Intro => {
+                color_current_alpha = 0.;
+                color_next_alpha = self.relative_progress(ahead);
+            }
+            TransitionType::Morph(_) => {
+                let fade_length = if reduced_motion {
+                    (REDUCED_MOTION_FADE / self.total_duration()).min(MORPH_FADE)
+                } else {
+                    MORPH_FADE
+                };
+                (color_current_alpha, color_next_alpha) =
+                    crossfade_alphas(self.relative_progress(ahead), 1., fade_length);
+            }
+        }
+
+        let color_current = Color {
+            r: 1.,
+            g: 1.,
+            b: 1.,
+            a: color_current_alpha,
+        };
+        let color_next = Color {
+            r: 1.,
+            g: 1.,
+            b: 1.,
+            a: color_next_alpha,
+        };
+
+        (color_current, color_next)
+    }
+
+    /// How the current and next state are warped `ahead` seconds from now, which is only ever something
+    /// but the identity in a morph. The outgoing creature grows and turns away from where it sat while
+    /// the incoming one comes from the other way, both around the center of their placement, so that the
+    /// new one is back at exactly its place and size once the morph is done. None with `reduced_motion`.
+    pub fn warps(&self, ahead: f32, reduced_motion: bool) -> (Warp, Warp) {
+        if !matches!(self.t_type, TransitionType::Morph(_)) || reduced_motion {
+            return (Warp::IDENTITY, Warp::IDENTITY);
+        }
+        let p = self.relative_progress(ahead);
+        let eased = p * p * (3. - 2. * p);
+        let left = 1. - eased;
+        (
+            Warp {
+                scale: 1. + MORPH_GROWTH * eased,
+                rotation: MORPH_TWIST * eased,
+            },
+            Warp {
+                scale: 1. - MORPH_GROWTH * left,
+                rotation: -MORPH_TWIST * left,
+            },
+        )
+    }
+
+    /// Triggers the sound in the first frame that gets past its trigger point, however far past,
+    /// so that a frame skipping over the point (or a transition starting behind it) can't lose it.
+    fn update_sound_to_play(&mut self) {
+        let sound_start = self.total_duration() * self.timing.sound_at;
+        self.sound_trigger = !self.sound_fired && self.time_progressed >= sound_start;
+        self.sound_fired |= self.sound_trigger;
+    }
+
+    pub fn sound_to_play(&self, rng: &mut Rng, graph: &StateGraph) -> Option<SoundIndex> {
+        if self.sound_trigger {
+            match (self.t_type, self.style.sound) {
+                (_, ReturnSound::Silent) => None,
+                (_, ReturnSound::Play(sound)) => Some(sound),
+                (
+                    TransitionType::Regular(_)
+                    | TransitionType::Return(_)
+                    | TransitionType::Morph(_),
+                    ReturnSound::Scale,
+                ) => Some(if rng.coin_flip() {
+                    SoundIndex::Scale1
+                } else {
+                    SoundIndex::Scale2
+                }),
+                // the last crack before hatching gets the bigger sound, however many come before it
+                (TransitionType::EggCracking(_), _) => match graph.crack_stage(self.goal_state) {
+                    Some((stage, stages)) if stage + 1 == stages => Some(SoundIndex::Crack2),
+                    Some(_) => Some(SoundIndex::Crack1),
+                    None => panic!("sound for crack requested but goal is no crack"),
+                },
+                (TransitionType::Intro, _) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn completed(&self) -> bool {
+        self.time_progressed >= self.total_duration()
+    }
+
+    /// a subsequent transition only exists for egg crack transitions, which start another crack,
+    /// or a regular transition to whatever hatches
+    pub fn subsequent_transition(
+        &self,
+        config: &TransitionConfig,
+        graph: &StateGraph,
+    ) -> Option<Transition> {
+        let TransitionType::EggCracking(b_type) = self.t_type else {
+            return None;
+        };
+        let next_state = graph.next(self.goal_state, b_type).unwrap_or_else(|| {
+            panic!(
+                "{:?} doesn't lead anywhere from {:?}",
+                b_type, self.goal_state
+            )
+        });
+        let t_type = if graph.is_crack(next_state) {
+            self.t_type
+        } else if config.morphs(self.goal_state, next_state) {
+            TransitionType::Morph(b_type)
+        } else {
+            TransitionType::Regular(Some(b_type))
+        };
+        Some(Transition::new(next_state, t_type, config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morphs_end_on_the_new_creature_unwarped() {
+        let t_type = TransitionType::Morph(ButtonType::Sun);
+        let mut t = Transition::new(WorldState::Chick, t_type, &TransitionConfig::default());
+        assert_eq!(t.warps(0., false).0, Warp::IDENTITY);
+        assert_eq!(t.colors(0., false).0.a, 1.);
+        t.progress(t.total_duration() / 2.);
+        let (current, next) = t.warps(0., false);
+        assert!(current.scale > 1. && next.scale < 1.);
+        assert_ne!(current.rotation, 0.);
+        assert_eq!(t.warps(0., true), (Warp::IDENTITY, Warp::IDENTITY));
+        t.progress(t.total_duration());
+        assert_eq!(t.warps(0., false).1, Warp::IDENTITY);
+        assert_eq!(t.colors(0., false).1.a, 1.);
+    }
+
+    /// how many frames of stepping a regular transition by `delta` triggered its sound
+    fn sound_triggers(duration: f32, delta: f32) -> usize {
+        let mut config = TransitionConfig::default();
+        config.regular.duration = duration;
+        let mut t = Transition::new(
+            WorldState::Duckling,
+            TransitionType::Regular(Some(ButtonType::Water)),
+            &config,
+        );
+        let mut triggers = 0;
+        while !t.completed() {
+            t.progress(delta);
+            triggers += t.sound_trigger as usize;
+        }
+        triggers
+    }
+
+    #[test]
+    fn crossfades_start_and_end_on_exactly_one_state() {
+        for overlap in [0., 0.3, 1.] {
+            assert_eq!(crossfade_alphas(0., overlap, CROSSFADE_FADE), (1., 0.));
+            assert_eq!(crossfade_alphas(1., overlap, CROSSFADE_FADE), (0., 1.));
+        }
+    }
+
+    #[test]
+    fn overlapping_crossfades_show_both_at_the_midpoint() {
+        let (current, next) = crossfade_alphas(0.5, 0., CROSSFADE_FADE);
+        assert!(current < 1e-6 && next < 1e-6);
+        let (current, next) = crossfade_alphas(0.5, 1., CROSSFADE_FADE);
+        assert!((current - 0.5).abs() < 1e-6 && (next - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn short_fades_cut_close_to_the_midpoint() {
+        let fade_length = 0.02;
+        assert_eq!(crossfade_alphas(0.45, 0., fade_length), (1., 0.));
+        assert_eq!(crossfade_alphas(0.55, 0., fade_length), (0., 1.));
+    }
+
+    #[test]
+    fn sounds_trigger_once_at_tiny_deltas() {
+        assert_eq!(sound_triggers(0.05, 1e-5), 1);
+        assert_eq!(sound_triggers(10., 1. / 240.), 1);
+    }
+
+    #[test]
+    fn sounds_trigger_even_when_a_frame_jumps_over_the_whole_transition() {
+        assert_eq!(sound_triggers(0.01, 1.), 1);
+        assert_eq!(sound_triggers(10., 100.), 1);
+    }
+
+    #[test]
+    fn returns_to_the_egg_go_through_black_for_as_long_as_configured() {
+        let mut config = TransitionConfig::default();
+        config.returns[WorldState::Duck as usize] = ReturnTransition {
+            duration: Some(6.),
+            black: 2.,
+            sound: ReturnSound::Silent,
+        };
+        let mut t = Transition::new(
+            WorldState::Egg,
+            TransitionType::Return(WorldState::Duck),
+            &config,
+        );
+        assert_eq!(t.total_duration(), 6.);
+        let mut rng = Rng::new(0);
+        for (at, current, next) in [(2.1, 0., 0.), (3., 0., 0.), (3.9, 0., 0.), (6., 0., 1.)] {
+            t.time_progressed = at;
+            let (c, n) = t.colors(0., false);
+            assert_eq!((c.a, n.a), (current, next), "at {}", at);
+        }
+        t.time_progressed = 0.;
+        while !t.completed() {
+            t.progress(0.1);
+            assert!(t.sound_to_play(&mut rng, &StateGraph::default()).is_none());
+        }
+        // without any set up it's just a regular transition into the egg
+        let t = Transition::new(
+            WorldState::Egg,
+            TransitionType::Return(WorldState::Duck),
+            &TransitionConfig::default(),
+        );
+        assert_eq!(
+            t.total_duration(),
+            TransitionConfig::default().regular.duration
+        );
+    }
+
+    #[test]
+    fn zero_durations_complete_at_once_without_nan() {
+        let mut config = TransitionConfig::default();
+        config.regular.duration = 0.;
+        let mut t = Transition::new(
+            WorldState::Duckling,
+            TransitionType::Regular(Some(ButtonType::Water)),
+            &config,
+        );
+        let (current, next) = t.colors(0., false);
+        assert!(!current.a.is_nan() && !next.a.is_nan());
+        t.progress(1. / 60.);
+        assert!(t.completed());
+        assert!(t.sound_trigger);
+        assert_eq!(t.colors(0., false).1.a, 1.);
+    }
+}
@@ -0,0 +1,123 @@
+//! The camera mapping the world onto the window.
+//!
+//! Both cameras keep the world inside a safe area, inset from every edge of the window by a fraction
+//! of its size, for displays cutting off their edges. Everything around it is simply more of the
+//! background. Input is mapped back through the same camera, so hit-testing follows along.
+//! Switching between views eases the camera over with a [`CameraTween`].
+
+use macroquad::prelude::*;
+
+use crate::{WORLD_HEIGHT, WORLD_WIDTH};
+
+/// The world stretched over the whole window (inside the safe area), the way it has always been shown.
+pub fn stretched_camera(inset: f32) -> Camera2D {
+    let inside = in_safe_area(inset);
+    let shown = vec2(WORLD_WIDTH, WORLD_HEIGHT) / inside;
+    camera_for(Rect::new(
+        (WORLD_WIDTH - shown.x) / 2.,
+        (WORLD_HEIGHT - shown.y) / 2.,
+        shown.x,
+        shown.y,
+    ))
+}
+
+/// The world centered in the window at a scale where every texel of a texture of `texture_size`
+/// (drawn over the whole world) lands on exactly one pixel, or on a whole number of pixels,
+/// or a whole number of texels on one pixel if the window is too small for that.
+pub fn pixel_perfect_camera(texture_size: Vec2, inset: f32) -> Camera2D {
+    let dpi = screen_dpi_scale();
+    let pixels = vec2(screen_width() * dpi, screen_height() * dpi);
+    let safe = pixels * in_safe_area(inset);
+    let fit = (safe.x / texture_size.x).min(safe.y / texture_size.y);
+    let scale = if fit >= 1. {
+        fit.floor()
+    } else {
+        1. / (1. / fit).ceil()
+    };
+    let pixels_per_unit = scale * texture_size.x / WORLD_WIDTH;
+    // show more than the world, so that it ends up the right size in the middle
+    let shown = pixels / pixels_per_unit;
+    camera_for(Rect::new(
+        (WORLD_WIDTH - shown.x) / 2.,
+        (WORLD_HEIGHT - shown.y) / 2.,
+        shown.x,
+        shown.y,
+    ))
+}
+
+/// how long the camera takes to ease over into a new view, in seconds
+const TWEEN_DURATION: f32 = 0.4;
+
+/// Eases the camera from one view into the next whenever the view changes while the window keeps its size.
+/// A resize jumps right away instead, the old view wouldn't fit the new window anyway, and so does
+/// everything with reduced motion.
+pub struct CameraTween {
+    /// target and zoom of the camera the tween started from
+    from: (Vec2, Vec2),
+    /// target and zoom of the camera it's going to, None before the first frame
+    to: Option<(Vec2, Vec2)>,
+    /// 0 at `from`, 1 at `to`
+    progress: f32,
+    /// the window size `to` was made for
+    screen: Vec2,
+}
+
+impl CameraTween {
+    pub fn new() -> Self {
+        Self {
+            from: (Vec2::ZERO, Vec2::ONE),
+            to: None,
+            progress: 1.,
+            screen: Vec2::ZERO,
+        }
+    }
+
+    /// The camera to show this frame with, `delta` seconds further on the way to `target`.
+    pub fn update(&mut self, target: Camera2D, delta: f32, reduced_motion: bool) -> Camera2D {
+        let goal = (target.target, target.zoom);
+        let screen = vec2(screen_width(), screen_height());
+        if self.to != Some(goal) {
+            let jump = reduced_motion || self.to.is_none() || screen != self.screen;
+            self.from = if jump { goal } else { self.current() };
+            self.progress = if jump { 1. } else { 0. };
+            self.to = Some(goal);
+            self.screen = screen;
+        }
+        if reduced_motion {
+            self.progress = 1.;
+        }
+        self.progress = (self.progress + delta / TWEEN_DURATION).min(1.);
+        let (target_pos, zoom) = self.current();
+        Camera2D {
+            target: target_pos,
+            zoom,
+            ..target
+        }
+    }
+
+    /// target and zoom at the current progress, easing in and out
+    fn current(&self) -> (Vec2, Vec2) {
+        let Some(to) = self.to else {
+            return self.from;
+        };
+        let t = self.progress * self.progress * (3. - 2. * self.progress);
+        (self.from.0.lerp(to.0, t), self.from.1.lerp(to.1, t))
+    }
+}
+
+/// How many logical pixels one world unit covers on screen under `cam`, along the tighter axis.
+pub fn pixels_per_unit(cam: &Camera2D) -> f32 {
+    // the zoom maps the shown world onto -1..1 in both directions
+    (cam.zoom.x.abs() * screen_width()).min(cam.zoom.y.abs() * screen_height()) / 2.
+}
+
+/// the share of the window's width and height that's left inside insets of `inset` on both sides
+fn in_safe_area(inset: f32) -> f32 {
+    (1. - 2. * inset).max(0.1)
+}
+
+fn camera_for(rect: Rect) -> Camera2D {
+    let mut cam = Camera2D::from_display_rect(rect);
+    cam.zoom = Vec2::new(cam.zoom.x, -cam.zoom.y); // workaround for https://github.com/not-fl3/macroquad/issues/171
+    cam
+}